@@ -0,0 +1,538 @@
+use crate::client::{Client, LoginResult};
+use crate::models::{
+    AllSetting, ClientRequest, ClientStats, ConfigJson, CpuHistoryPoint, CreateInboundRequest,
+    EchCert, Inbounds, Mldsa65, Mlkem768, OutboundTraffic, ServerStatus, Uuid, VlessEnc, WarpData,
+    WarpDevice, WarpRegistration, X25519Cert,
+};
+use crate::version::PanelVersion;
+use crate::{ClientIpsResponse, Result};
+use futures::future::BoxFuture;
+use std::collections::BTreeMap;
+use std::time::SystemTime;
+
+/// Every panel operation, implemented by [`Client`].
+///
+/// Object safe, so services can depend on `Arc<dyn PanelApi>` and be tested with a fake
+/// implementation or wrapped by decorators.
+pub trait PanelApi: Send + Sync {
+    /// Log in with the configured credentials.
+    fn login(&self) -> BoxFuture<'_, Result<LoginResult>>;
+
+    /// Version of the connected panel, if it can be detected.
+    fn panel_version(&self) -> BoxFuture<'_, Result<Option<PanelVersion>>>;
+
+    /// List all inbounds.
+    fn get_inbounds_list(&self) -> BoxFuture<'_, Result<Vec<Inbounds>>>;
+
+    /// Get inbound by id.
+    fn get_inbound_by_id(&self, inbound_id: u64) -> BoxFuture<'_, Result<Inbounds>>;
+
+    /// Get client traffic by email.
+    fn get_client_traffic_by_email<'a>(
+        &'a self,
+        email: &'a str,
+    ) -> BoxFuture<'a, Result<ClientStats>>;
+
+    /// Get client traffic by id.
+    fn get_client_traffic_by_id<'a>(
+        &'a self,
+        id: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<ClientStats>>>;
+
+    /// Trigger backup via bot.
+    fn send_backup_by_bot(&self) -> BoxFuture<'_, Result<()>>;
+
+    /// Get client IPs by email.
+    fn get_client_ips<'a>(
+        &'a self,
+        client_email: &'a str,
+    ) -> BoxFuture<'a, Result<ClientIpsResponse>>;
+
+    /// Create inbound.
+    fn add_inbound<'a>(&'a self, req: &'a CreateInboundRequest) -> BoxFuture<'a, Result<Inbounds>>;
+
+    /// Add client(s) to inbound.
+    fn add_client_to_inbound<'a>(
+        &'a self,
+        req: &'a ClientRequest,
+    ) -> BoxFuture<'a, Result<Option<()>>>;
+
+    /// Update inbound.
+    fn update_inbound<'a>(
+        &'a self,
+        inbound_id: u64,
+        req: &'a CreateInboundRequest,
+    ) -> BoxFuture<'a, Result<Inbounds>>;
+
+    /// Update client by UUID.
+    fn update_client<'a>(
+        &'a self,
+        uuid: &'a str,
+        req: &'a ClientRequest,
+    ) -> BoxFuture<'a, Result<Option<()>>>;
+
+    /// Clear client IPs by email.
+    fn clear_client_ips<'a>(&'a self, email: &'a str) -> BoxFuture<'a, Result<Option<()>>>;
+
+    /// Reset all inbound traffics.
+    fn reset_all_inbound_traffics(&self) -> BoxFuture<'_, Result<Option<()>>>;
+
+    /// Reset all client traffics for inbound.
+    fn reset_all_client_traffics(&self, inbound_id: u64) -> BoxFuture<'_, Result<Option<()>>>;
+
+    /// Reset client traffic by email.
+    fn reset_client_traffic<'a>(
+        &'a self,
+        inbound_id: u64,
+        email: &'a str,
+    ) -> BoxFuture<'a, Result<Option<()>>>;
+
+    /// Set a client's upload and download counters.
+    fn update_client_traffic<'a>(
+        &'a self,
+        email: &'a str,
+        up: u128,
+        down: u128,
+    ) -> BoxFuture<'a, Result<()>>;
+
+    /// Delete client by UUID.
+    fn delete_client<'a>(
+        &'a self,
+        inbound_id: u64,
+        uuid: &'a str,
+    ) -> BoxFuture<'a, Result<Option<()>>>;
+
+    /// Delete inbound by id.
+    fn delete_inbound(&self, inbound_id: u64) -> BoxFuture<'_, Result<u64>>;
+
+    /// Delete depleted clients by inbound.
+    fn delete_depleted_clients(&self, inbound_id: u64) -> BoxFuture<'_, Result<Option<()>>>;
+
+    /// List online clients.
+    fn online_clients(&self) -> BoxFuture<'_, Result<Option<Vec<String>>>>;
+
+    /// Import inbound.
+    fn import_inbound<'a>(&'a self, inbound: &'a Inbounds) -> BoxFuture<'a, Result<Inbounds>>;
+
+    /// When each client was last seen, by email.
+    fn get_last_online(&self) -> BoxFuture<'_, Result<BTreeMap<String, SystemTime>>>;
+
+    /// Last seen times, completed from the client stats of every inbound.
+    fn get_last_online_merged(&self) -> BoxFuture<'_, Result<BTreeMap<String, SystemTime>>>;
+
+    /// Delete client by email.
+    fn del_client_by_email<'a>(
+        &'a self,
+        inbound_id: u64,
+        email: &'a str,
+    ) -> BoxFuture<'a, Result<Option<()>>>;
+
+    /// Get server status.
+    fn server_status(&self) -> BoxFuture<'_, Result<Option<ServerStatus>>>;
+
+    /// Download server database.
+    fn server_get_db(&self) -> BoxFuture<'_, Result<Vec<u8>>>;
+
+    /// Get Xray versions.
+    fn get_xray_version(&self) -> BoxFuture<'_, Result<Option<Vec<String>>>>;
+
+    /// Get config JSON.
+    fn get_config_json(&self) -> BoxFuture<'_, Result<ConfigJson>>;
+
+    /// Get CPU history for a time bucket.
+    fn cpu_history(&self, minutes: u32) -> BoxFuture<'_, Result<Option<Vec<CpuHistoryPoint>>>>;
+
+    /// Request a new UUID.
+    fn get_new_uuid(&self) -> BoxFuture<'_, Result<Uuid>>;
+
+    /// Request a new X25519 certificate.
+    fn get_new_x25519_cert(&self) -> BoxFuture<'_, Result<X25519Cert>>;
+
+    /// Request a new MLDsa65 bundle.
+    fn get_new_mldsa65(&self) -> BoxFuture<'_, Result<Mldsa65>>;
+
+    /// Request a new MLKEM768 bundle.
+    fn get_new_mlkem768(&self) -> BoxFuture<'_, Result<Mlkem768>>;
+
+    /// Request VLESS encryption settings.
+    fn get_new_vless_enc(&self) -> BoxFuture<'_, Result<VlessEnc>>;
+
+    /// Request a new ECH certificate.
+    fn get_new_ech_cert(&self) -> BoxFuture<'_, Result<EchCert>>;
+
+    /// Stop Xray service.
+    fn stop_xray_service(&self) -> BoxFuture<'_, Result<Option<()>>>;
+
+    /// Restart Xray service.
+    fn restart_xray_service(&self) -> BoxFuture<'_, Result<Option<()>>>;
+
+    /// Get all panel settings.
+    fn get_all_settings(&self) -> BoxFuture<'_, Result<AllSetting>>;
+
+    /// Replace the panel settings.
+    fn update_settings<'a>(&'a self, settings: &'a AllSetting)
+    -> BoxFuture<'a, Result<Option<()>>>;
+
+    /// Get the default Xray config.
+    fn get_default_xray_config(&self) -> BoxFuture<'_, Result<ConfigJson>>;
+
+    /// Restart the panel.
+    fn restart_panel(&self) -> BoxFuture<'_, Result<Option<()>>>;
+
+    /// Get the Xray config template.
+    fn get_xray_template(&self) -> BoxFuture<'_, Result<ConfigJson>>;
+
+    /// Replace the Xray config template.
+    fn update_xray_template<'a>(
+        &'a self,
+        template: &'a ConfigJson,
+    ) -> BoxFuture<'a, Result<Option<()>>>;
+
+    /// Error Xray reported on its last start, if any.
+    fn get_xray_result(&self) -> BoxFuture<'_, Result<Option<String>>>;
+
+    /// Get traffic per outbound, keyed by outbound tag.
+    fn get_outbounds_traffic(&self) -> BoxFuture<'_, Result<BTreeMap<String, OutboundTraffic>>>;
+
+    /// Reset the traffic counters of one outbound.
+    fn reset_outbound_traffic<'a>(&'a self, tag: &'a str) -> BoxFuture<'a, Result<()>>;
+
+    /// Reset the traffic counters of every outbound.
+    fn reset_all_outbounds_traffic(&self) -> BoxFuture<'_, Result<()>>;
+
+    /// Get the stored WARP account credentials.
+    fn get_warp_data(&self) -> BoxFuture<'_, Result<Option<WarpData>>>;
+
+    /// Forget the stored WARP account.
+    fn del_warp_data(&self) -> BoxFuture<'_, Result<()>>;
+
+    /// Fetch the registered WARP device and its WireGuard config.
+    fn get_warp_config(&self) -> BoxFuture<'_, Result<WarpDevice>>;
+
+    /// Register a new WARP device for a WireGuard key pair.
+    fn reg_warp<'a>(
+        &'a self,
+        private_key: &'a str,
+        public_key: &'a str,
+    ) -> BoxFuture<'a, Result<WarpRegistration>>;
+
+    /// Attach a WARP+ license key to the registered account.
+    fn set_warp_license<'a>(&'a self, license: &'a str) -> BoxFuture<'a, Result<WarpData>>;
+
+    /// Install Xray version.
+    fn install_xray_version<'a>(&'a self, version: &'a str) -> BoxFuture<'a, Result<Option<()>>>;
+
+    /// Update geofile bundle.
+    fn update_geofile(&self) -> BoxFuture<'_, Result<Option<()>>>;
+
+    /// Update geofile by name.
+    fn update_geofile_by_name<'a>(
+        &'a self,
+        file_name: &'a str,
+    ) -> BoxFuture<'a, Result<Option<()>>>;
+
+    /// Fetch server logs.
+    fn logs(&self, count: u32) -> BoxFuture<'_, Result<Vec<String>>>;
+
+    /// Fetch Xray logs.
+    fn xray_logs(&self, count: u32) -> BoxFuture<'_, Result<Option<Vec<String>>>>;
+
+    /// Import DB upload.
+    fn import_db_upload<'a>(
+        &'a self,
+        filename: &'a str,
+        bytes: Vec<u8>,
+    ) -> BoxFuture<'a, Result<String>>;
+}
+
+impl PanelApi for Client {
+    fn login(&self) -> BoxFuture<'_, Result<LoginResult>> {
+        Box::pin(Client::login(self))
+    }
+
+    fn panel_version(&self) -> BoxFuture<'_, Result<Option<PanelVersion>>> {
+        Box::pin(Client::panel_version(self))
+    }
+
+    fn get_inbounds_list(&self) -> BoxFuture<'_, Result<Vec<Inbounds>>> {
+        Box::pin(Client::get_inbounds_list(self))
+    }
+
+    fn get_inbound_by_id(&self, inbound_id: u64) -> BoxFuture<'_, Result<Inbounds>> {
+        Box::pin(Client::get_inbound_by_id(self, inbound_id))
+    }
+
+    fn get_client_traffic_by_email<'a>(
+        &'a self,
+        email: &'a str,
+    ) -> BoxFuture<'a, Result<ClientStats>> {
+        Box::pin(Client::get_client_traffic_by_email(self, email))
+    }
+
+    fn get_client_traffic_by_id<'a>(
+        &'a self,
+        id: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<ClientStats>>> {
+        Box::pin(Client::get_client_traffic_by_id(self, id))
+    }
+
+    fn send_backup_by_bot(&self) -> BoxFuture<'_, Result<()>> {
+        Box::pin(Client::send_backup_by_bot(self))
+    }
+
+    fn get_client_ips<'a>(
+        &'a self,
+        client_email: &'a str,
+    ) -> BoxFuture<'a, Result<ClientIpsResponse>> {
+        Box::pin(Client::get_client_ips(self, client_email))
+    }
+
+    fn add_inbound<'a>(&'a self, req: &'a CreateInboundRequest) -> BoxFuture<'a, Result<Inbounds>> {
+        Box::pin(Client::add_inbound(self, req))
+    }
+
+    fn add_client_to_inbound<'a>(
+        &'a self,
+        req: &'a ClientRequest,
+    ) -> BoxFuture<'a, Result<Option<()>>> {
+        Box::pin(Client::add_client_to_inbound(self, req))
+    }
+
+    fn update_inbound<'a>(
+        &'a self,
+        inbound_id: u64,
+        req: &'a CreateInboundRequest,
+    ) -> BoxFuture<'a, Result<Inbounds>> {
+        Box::pin(Client::update_inbound(self, inbound_id, req))
+    }
+
+    fn update_client<'a>(
+        &'a self,
+        uuid: &'a str,
+        req: &'a ClientRequest,
+    ) -> BoxFuture<'a, Result<Option<()>>> {
+        Box::pin(Client::update_client(self, uuid, req))
+    }
+
+    fn clear_client_ips<'a>(&'a self, email: &'a str) -> BoxFuture<'a, Result<Option<()>>> {
+        Box::pin(Client::clear_client_ips(self, email))
+    }
+
+    fn reset_all_inbound_traffics(&self) -> BoxFuture<'_, Result<Option<()>>> {
+        Box::pin(Client::reset_all_inbound_traffics(self))
+    }
+
+    fn reset_all_client_traffics(&self, inbound_id: u64) -> BoxFuture<'_, Result<Option<()>>> {
+        Box::pin(Client::reset_all_client_traffics(self, inbound_id))
+    }
+
+    fn reset_client_traffic<'a>(
+        &'a self,
+        inbound_id: u64,
+        email: &'a str,
+    ) -> BoxFuture<'a, Result<Option<()>>> {
+        Box::pin(Client::reset_client_traffic(self, inbound_id, email))
+    }
+
+    fn update_client_traffic<'a>(
+        &'a self,
+        email: &'a str,
+        up: u128,
+        down: u128,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(Client::update_client_traffic(self, email, up, down))
+    }
+
+    fn delete_client<'a>(
+        &'a self,
+        inbound_id: u64,
+        uuid: &'a str,
+    ) -> BoxFuture<'a, Result<Option<()>>> {
+        Box::pin(Client::delete_client(self, inbound_id, uuid))
+    }
+
+    fn delete_inbound(&self, inbound_id: u64) -> BoxFuture<'_, Result<u64>> {
+        Box::pin(Client::delete_inbound(self, inbound_id))
+    }
+
+    fn delete_depleted_clients(&self, inbound_id: u64) -> BoxFuture<'_, Result<Option<()>>> {
+        Box::pin(Client::delete_depleted_clients(self, inbound_id))
+    }
+
+    fn online_clients(&self) -> BoxFuture<'_, Result<Option<Vec<String>>>> {
+        Box::pin(Client::online_clients(self))
+    }
+
+    fn import_inbound<'a>(&'a self, inbound: &'a Inbounds) -> BoxFuture<'a, Result<Inbounds>> {
+        Box::pin(Client::import_inbound(self, inbound))
+    }
+
+    fn get_last_online(&self) -> BoxFuture<'_, Result<BTreeMap<String, SystemTime>>> {
+        Box::pin(Client::get_last_online(self))
+    }
+
+    fn get_last_online_merged(&self) -> BoxFuture<'_, Result<BTreeMap<String, SystemTime>>> {
+        Box::pin(Client::get_last_online_merged(self))
+    }
+
+    fn del_client_by_email<'a>(
+        &'a self,
+        inbound_id: u64,
+        email: &'a str,
+    ) -> BoxFuture<'a, Result<Option<()>>> {
+        Box::pin(Client::del_client_by_email(self, inbound_id, email))
+    }
+
+    fn server_status(&self) -> BoxFuture<'_, Result<Option<ServerStatus>>> {
+        Box::pin(Client::server_status(self))
+    }
+
+    fn server_get_db(&self) -> BoxFuture<'_, Result<Vec<u8>>> {
+        Box::pin(Client::server_get_db(self))
+    }
+
+    fn get_xray_version(&self) -> BoxFuture<'_, Result<Option<Vec<String>>>> {
+        Box::pin(Client::get_xray_version(self))
+    }
+
+    fn get_config_json(&self) -> BoxFuture<'_, Result<ConfigJson>> {
+        Box::pin(Client::get_config_json(self))
+    }
+
+    fn cpu_history(&self, minutes: u32) -> BoxFuture<'_, Result<Option<Vec<CpuHistoryPoint>>>> {
+        Box::pin(Client::cpu_history(self, minutes))
+    }
+
+    fn get_new_uuid(&self) -> BoxFuture<'_, Result<Uuid>> {
+        Box::pin(Client::get_new_uuid(self))
+    }
+
+    fn get_new_x25519_cert(&self) -> BoxFuture<'_, Result<X25519Cert>> {
+        Box::pin(Client::get_new_x25519_cert(self))
+    }
+
+    fn get_new_mldsa65(&self) -> BoxFuture<'_, Result<Mldsa65>> {
+        Box::pin(Client::get_new_mldsa65(self))
+    }
+
+    fn get_new_mlkem768(&self) -> BoxFuture<'_, Result<Mlkem768>> {
+        Box::pin(Client::get_new_mlkem768(self))
+    }
+
+    fn get_new_vless_enc(&self) -> BoxFuture<'_, Result<VlessEnc>> {
+        Box::pin(Client::get_new_vless_enc(self))
+    }
+
+    fn get_new_ech_cert(&self) -> BoxFuture<'_, Result<EchCert>> {
+        Box::pin(Client::get_new_ech_cert(self))
+    }
+
+    fn stop_xray_service(&self) -> BoxFuture<'_, Result<Option<()>>> {
+        Box::pin(Client::stop_xray_service(self))
+    }
+
+    fn restart_xray_service(&self) -> BoxFuture<'_, Result<Option<()>>> {
+        Box::pin(Client::restart_xray_service(self))
+    }
+
+    fn get_all_settings(&self) -> BoxFuture<'_, Result<AllSetting>> {
+        Box::pin(Client::get_all_settings(self))
+    }
+
+    fn update_settings<'a>(
+        &'a self,
+        settings: &'a AllSetting,
+    ) -> BoxFuture<'a, Result<Option<()>>> {
+        Box::pin(Client::update_settings(self, settings))
+    }
+
+    fn get_default_xray_config(&self) -> BoxFuture<'_, Result<ConfigJson>> {
+        Box::pin(Client::get_default_xray_config(self))
+    }
+
+    fn restart_panel(&self) -> BoxFuture<'_, Result<Option<()>>> {
+        Box::pin(Client::restart_panel(self))
+    }
+
+    fn get_xray_template(&self) -> BoxFuture<'_, Result<ConfigJson>> {
+        Box::pin(Client::get_xray_template(self))
+    }
+
+    fn update_xray_template<'a>(
+        &'a self,
+        template: &'a ConfigJson,
+    ) -> BoxFuture<'a, Result<Option<()>>> {
+        Box::pin(Client::update_xray_template(self, template))
+    }
+
+    fn get_xray_result(&self) -> BoxFuture<'_, Result<Option<String>>> {
+        Box::pin(Client::get_xray_result(self))
+    }
+
+    fn get_outbounds_traffic(&self) -> BoxFuture<'_, Result<BTreeMap<String, OutboundTraffic>>> {
+        Box::pin(Client::get_outbounds_traffic(self))
+    }
+
+    fn reset_outbound_traffic<'a>(&'a self, tag: &'a str) -> BoxFuture<'a, Result<()>> {
+        Box::pin(Client::reset_outbound_traffic(self, tag))
+    }
+
+    fn reset_all_outbounds_traffic(&self) -> BoxFuture<'_, Result<()>> {
+        Box::pin(Client::reset_all_outbounds_traffic(self))
+    }
+
+    fn get_warp_data(&self) -> BoxFuture<'_, Result<Option<WarpData>>> {
+        Box::pin(Client::get_warp_data(self))
+    }
+
+    fn del_warp_data(&self) -> BoxFuture<'_, Result<()>> {
+        Box::pin(Client::del_warp_data(self))
+    }
+
+    fn get_warp_config(&self) -> BoxFuture<'_, Result<WarpDevice>> {
+        Box::pin(Client::get_warp_config(self))
+    }
+
+    fn reg_warp<'a>(
+        &'a self,
+        private_key: &'a str,
+        public_key: &'a str,
+    ) -> BoxFuture<'a, Result<WarpRegistration>> {
+        Box::pin(Client::reg_warp(self, private_key, public_key))
+    }
+
+    fn set_warp_license<'a>(&'a self, license: &'a str) -> BoxFuture<'a, Result<WarpData>> {
+        Box::pin(Client::set_warp_license(self, license))
+    }
+
+    fn install_xray_version<'a>(&'a self, version: &'a str) -> BoxFuture<'a, Result<Option<()>>> {
+        Box::pin(Client::install_xray_version(self, version))
+    }
+
+    fn update_geofile(&self) -> BoxFuture<'_, Result<Option<()>>> {
+        Box::pin(Client::update_geofile(self))
+    }
+
+    fn update_geofile_by_name<'a>(
+        &'a self,
+        file_name: &'a str,
+    ) -> BoxFuture<'a, Result<Option<()>>> {
+        Box::pin(Client::update_geofile_by_name(self, file_name))
+    }
+
+    fn logs(&self, count: u32) -> BoxFuture<'_, Result<Vec<String>>> {
+        Box::pin(Client::logs(self, count))
+    }
+
+    fn xray_logs(&self, count: u32) -> BoxFuture<'_, Result<Option<Vec<String>>>> {
+        Box::pin(Client::xray_logs(self, count))
+    }
+
+    fn import_db_upload<'a>(
+        &'a self,
+        filename: &'a str,
+        bytes: Vec<u8>,
+    ) -> BoxFuture<'a, Result<String>> {
+        Box::pin(Client::import_db_upload(self, filename, bytes))
+    }
+}
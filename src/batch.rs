@@ -0,0 +1,49 @@
+use crate::Result;
+use futures::future::{self, Future};
+use futures::stream::{self, BoxStream, StreamExt};
+
+/// Concurrency used by batch helpers when `max_in_flight` is not set.
+pub const DEFAULT_BATCH_CONCURRENCY: usize = 4;
+
+/// How batch helpers react to a failed key.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BatchMode {
+    /// Yield a result for every key.
+    #[default]
+    Continue,
+    /// End the stream after the first error, cancelling requests still in flight.
+    StopOnError,
+}
+
+/// Run `f` for every key with at most `limit` calls at once, yielding in completion order.
+pub(crate) fn run<'a, T, F, Fut>(
+    keys: Vec<String>,
+    limit: usize,
+    mode: BatchMode,
+    f: F,
+) -> BoxStream<'a, (String, Result<T>)>
+where
+    T: Send + 'a,
+    F: Fn(String) -> Fut + Send + 'a,
+    Fut: Future<Output = Result<T>> + Send + 'a,
+{
+    let results = stream::iter(keys)
+        .map(move |key| {
+            let call = f(key.clone());
+            async move { (key, call.await) }
+        })
+        .buffer_unordered(limit.max(1));
+    match mode {
+        BatchMode::Continue => results.boxed(),
+        BatchMode::StopOnError => {
+            let mut failed = false;
+            results
+                .take_while(move |(_, result)| {
+                    let more = !failed;
+                    failed |= result.is_err();
+                    future::ready(more)
+                })
+                .boxed()
+        }
+    }
+}
@@ -0,0 +1,433 @@
+//! Synchronous wrapper around the async [`Client`](crate::Client), behind the `blocking` feature.
+//!
+//! Each blocking client owns a small Tokio runtime. Do not call it from inside an async
+//! runtime; use the async client there instead.
+
+use crate::batch::BatchMode;
+use crate::builder::ClientBuilder;
+use crate::client::{AuthToken, ClientOptions, LoginResult};
+use crate::error::Error;
+use crate::models::{
+    AllSetting, ClientRequest, ClientStats, ConfigJson, CpuHistoryPoint, CreateInboundRequest,
+    EchCert, Inbounds, Mldsa65, Mlkem768, OutboundTraffic, ServerStatus, Uuid, VlessEnc, WarpData,
+    WarpDevice, WarpRegistration, X25519Cert,
+};
+use crate::version::{Capabilities, PanelVersion};
+use crate::{ClientIpsResponse, Result};
+use futures::StreamExt;
+use reqwest::IntoUrl;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::time::SystemTime;
+use tokio::runtime::{Builder, Runtime};
+
+/// Blocking panel client with the same operations, models and errors as the async one.
+#[derive(Debug)]
+pub struct Client {
+    inner: crate::Client,
+    runtime: Runtime,
+}
+
+impl Client {
+    /// Create a client and log in.
+    pub fn new(
+        username: impl Into<String>,
+        password: impl Into<String>,
+        url: impl IntoUrl,
+    ) -> Result<Self> {
+        Self::new_with_options(username, password, url, ClientOptions::default())
+    }
+
+    /// Create a client with custom options and log in.
+    pub fn new_with_options(
+        username: impl Into<String>,
+        password: impl Into<String>,
+        url: impl IntoUrl,
+        options: ClientOptions,
+    ) -> Result<Self> {
+        let runtime = runtime()?;
+        let inner = runtime.block_on(crate::Client::new_with_options(
+            username, password, url, options,
+        ))?;
+        Ok(Self { inner, runtime })
+    }
+
+    /// Create a client authenticated by an existing bearer token, without logging in.
+    pub fn from_token(token: AuthToken, url: impl IntoUrl, options: ClientOptions) -> Result<Self> {
+        Self::from_builder(crate::Client::builder(url).token(token).options(options))
+    }
+
+    /// Build from a configured [`ClientBuilder`]; login happens on the first call.
+    pub fn from_builder(builder: ClientBuilder) -> Result<Self> {
+        let runtime = runtime()?;
+        let inner = {
+            let _guard = runtime.enter();
+            builder.build()?
+        };
+        Ok(Self { inner, runtime })
+    }
+
+    /// The wrapped async client.
+    pub fn inner(&self) -> &crate::Client {
+        &self.inner
+    }
+
+    /// Log in with the configured credentials, replacing any current session.
+    pub fn login(&self) -> Result<LoginResult> {
+        self.block_on(self.inner.login())
+    }
+
+    /// Current bearer token, if any.
+    pub fn token(&self) -> Option<AuthToken> {
+        self.inner.token()
+    }
+
+    /// Replace the bearer token used for subsequent calls.
+    pub fn set_token(&self, token: Option<AuthToken>) {
+        self.inner.set_token(token)
+    }
+
+    /// Version of the connected panel, detected once and cached.
+    pub fn panel_version(&self) -> Result<Option<PanelVersion>> {
+        self.block_on(self.inner.panel_version())
+    }
+
+    /// Endpoints the connected panel supports, probed once and cached.
+    pub fn capabilities(&self) -> Result<Capabilities> {
+        self.block_on(self.inner.capabilities())
+    }
+
+    /// Get traffic for many emails, at most `max_in_flight` at once, in completion order.
+    pub fn get_client_traffic_batch<I>(
+        &self,
+        emails: I,
+        mode: BatchMode,
+    ) -> Vec<(String, Result<ClientStats>)>
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.runtime
+            .block_on(self.inner.get_client_traffic_batch(emails, mode).collect())
+    }
+
+    /// Get IPs for many emails, at most `max_in_flight` at once, in completion order.
+    pub fn get_client_ips_batch<I>(
+        &self,
+        emails: I,
+        mode: BatchMode,
+    ) -> Vec<(String, Result<ClientIpsResponse>)>
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.runtime
+            .block_on(self.inner.get_client_ips_batch(emails, mode).collect())
+    }
+
+    /// List all inbounds.
+    pub fn get_inbounds_list(&self) -> Result<Vec<Inbounds>> {
+        self.block_on(self.inner.get_inbounds_list())
+    }
+
+    /// Get inbound by id.
+    pub fn get_inbound_by_id(&self, inbound_id: u64) -> Result<Inbounds> {
+        self.block_on(self.inner.get_inbound_by_id(inbound_id))
+    }
+
+    /// Get client traffic by email.
+    pub fn get_client_traffic_by_email(&self, email: &str) -> Result<ClientStats> {
+        self.block_on(self.inner.get_client_traffic_by_email(email))
+    }
+
+    /// Get client traffic by id.
+    pub fn get_client_traffic_by_id(&self, id: &str) -> Result<Vec<ClientStats>> {
+        self.block_on(self.inner.get_client_traffic_by_id(id))
+    }
+
+    /// Trigger backup via bot.
+    pub fn send_backup_by_bot(&self) -> Result<()> {
+        self.block_on(self.inner.send_backup_by_bot())
+    }
+
+    /// Get client IPs by email.
+    pub fn get_client_ips(&self, client_email: &str) -> Result<ClientIpsResponse> {
+        self.block_on(self.inner.get_client_ips(client_email))
+    }
+
+    /// Create inbound.
+    pub fn add_inbound(&self, req: &CreateInboundRequest) -> Result<Inbounds> {
+        self.block_on(self.inner.add_inbound(req))
+    }
+
+    /// Add client(s) to inbound.
+    pub fn add_client_to_inbound(&self, req: &ClientRequest) -> Result<Option<()>> {
+        self.block_on(self.inner.add_client_to_inbound(req))
+    }
+
+    /// Update inbound.
+    pub fn update_inbound(&self, inbound_id: u64, req: &CreateInboundRequest) -> Result<Inbounds> {
+        self.block_on(self.inner.update_inbound(inbound_id, req))
+    }
+
+    /// Update client by UUID.
+    pub fn update_client(&self, uuid: &str, req: &ClientRequest) -> Result<Option<()>> {
+        self.block_on(self.inner.update_client(uuid, req))
+    }
+
+    /// Clear client IPs by email.
+    pub fn clear_client_ips(&self, email: &str) -> Result<Option<()>> {
+        self.block_on(self.inner.clear_client_ips(email))
+    }
+
+    /// Reset all inbound traffics.
+    pub fn reset_all_inbound_traffics(&self) -> Result<Option<()>> {
+        self.block_on(self.inner.reset_all_inbound_traffics())
+    }
+
+    /// Reset all client traffics for inbound.
+    pub fn reset_all_client_traffics(&self, inbound_id: u64) -> Result<Option<()>> {
+        self.block_on(self.inner.reset_all_client_traffics(inbound_id))
+    }
+
+    /// Reset client traffic by email.
+    pub fn reset_client_traffic(&self, inbound_id: u64, email: &str) -> Result<Option<()>> {
+        self.block_on(self.inner.reset_client_traffic(inbound_id, email))
+    }
+
+    /// Set a client's upload and download counters.
+    pub fn update_client_traffic(&self, email: &str, up: u128, down: u128) -> Result<()> {
+        self.block_on(self.inner.update_client_traffic(email, up, down))
+    }
+
+    /// Delete client by UUID.
+    pub fn delete_client(&self, inbound_id: u64, uuid: &str) -> Result<Option<()>> {
+        self.block_on(self.inner.delete_client(inbound_id, uuid))
+    }
+
+    /// Delete inbound by id.
+    pub fn delete_inbound(&self, inbound_id: u64) -> Result<u64> {
+        self.block_on(self.inner.delete_inbound(inbound_id))
+    }
+
+    /// Delete depleted clients by inbound.
+    pub fn delete_depleted_clients(&self, inbound_id: u64) -> Result<Option<()>> {
+        self.block_on(self.inner.delete_depleted_clients(inbound_id))
+    }
+
+    /// List online clients.
+    pub fn online_clients(&self) -> Result<Option<Vec<String>>> {
+        self.block_on(self.inner.online_clients())
+    }
+
+    /// Import inbound.
+    pub fn import_inbound(&self, inbound: &Inbounds) -> Result<Inbounds> {
+        self.block_on(self.inner.import_inbound(inbound))
+    }
+
+    /// When each client was last seen, by email.
+    pub fn get_last_online(&self) -> Result<BTreeMap<String, SystemTime>> {
+        self.block_on(self.inner.get_last_online())
+    }
+
+    /// Last seen times, completed from the client stats of every inbound.
+    pub fn get_last_online_merged(&self) -> Result<BTreeMap<String, SystemTime>> {
+        self.block_on(self.inner.get_last_online_merged())
+    }
+
+    /// Delete client by email.
+    pub fn del_client_by_email(&self, inbound_id: u64, email: &str) -> Result<Option<()>> {
+        self.block_on(self.inner.del_client_by_email(inbound_id, email))
+    }
+
+    /// Get server status.
+    pub fn server_status(&self) -> Result<Option<ServerStatus>> {
+        self.block_on(self.inner.server_status())
+    }
+
+    /// Download server database.
+    pub fn server_get_db(&self) -> Result<Vec<u8>> {
+        self.block_on(self.inner.server_get_db())
+    }
+
+    /// Get Xray versions.
+    pub fn get_xray_version(&self) -> Result<Option<Vec<String>>> {
+        self.block_on(self.inner.get_xray_version())
+    }
+
+    /// Get config JSON.
+    pub fn get_config_json(&self) -> Result<ConfigJson> {
+        self.block_on(self.inner.get_config_json())
+    }
+
+    /// Get CPU history for a time bucket.
+    pub fn cpu_history(&self, minutes: u32) -> Result<Option<Vec<CpuHistoryPoint>>> {
+        self.block_on(self.inner.cpu_history(minutes))
+    }
+
+    /// Request a new UUID.
+    pub fn get_new_uuid(&self) -> Result<Uuid> {
+        self.block_on(self.inner.get_new_uuid())
+    }
+
+    /// Request a new X25519 certificate.
+    pub fn get_new_x25519_cert(&self) -> Result<X25519Cert> {
+        self.block_on(self.inner.get_new_x25519_cert())
+    }
+
+    /// Request a new MLDsa65 bundle.
+    pub fn get_new_mldsa65(&self) -> Result<Mldsa65> {
+        self.block_on(self.inner.get_new_mldsa65())
+    }
+
+    /// Request a new MLKEM768 bundle.
+    pub fn get_new_mlkem768(&self) -> Result<Mlkem768> {
+        self.block_on(self.inner.get_new_mlkem768())
+    }
+
+    /// Request VLESS encryption settings.
+    pub fn get_new_vless_enc(&self) -> Result<VlessEnc> {
+        self.block_on(self.inner.get_new_vless_enc())
+    }
+
+    /// Request a new ECH certificate.
+    pub fn get_new_ech_cert(&self) -> Result<EchCert> {
+        self.block_on(self.inner.get_new_ech_cert())
+    }
+
+    /// Stop Xray service.
+    pub fn stop_xray_service(&self) -> Result<Option<()>> {
+        self.block_on(self.inner.stop_xray_service())
+    }
+
+    /// Restart Xray service.
+    pub fn restart_xray_service(&self) -> Result<Option<()>> {
+        self.block_on(self.inner.restart_xray_service())
+    }
+
+    /// Get all panel settings.
+    pub fn get_all_settings(&self) -> Result<AllSetting> {
+        self.block_on(self.inner.get_all_settings())
+    }
+
+    /// Replace the panel settings.
+    pub fn update_settings(&self, settings: &AllSetting) -> Result<Option<()>> {
+        self.block_on(self.inner.update_settings(settings))
+    }
+
+    /// Get the default Xray config.
+    pub fn get_default_xray_config(&self) -> Result<ConfigJson> {
+        self.block_on(self.inner.get_default_xray_config())
+    }
+
+    /// Restart the panel.
+    pub fn restart_panel(&self) -> Result<Option<()>> {
+        self.block_on(self.inner.restart_panel())
+    }
+
+    /// Get the Xray config template.
+    pub fn get_xray_template(&self) -> Result<ConfigJson> {
+        self.block_on(self.inner.get_xray_template())
+    }
+
+    /// Replace the Xray config template.
+    pub fn update_xray_template(&self, template: &ConfigJson) -> Result<Option<()>> {
+        self.block_on(self.inner.update_xray_template(template))
+    }
+
+    /// Error Xray reported on its last start, if any.
+    pub fn get_xray_result(&self) -> Result<Option<String>> {
+        self.block_on(self.inner.get_xray_result())
+    }
+
+    /// Modify the Xray template, save it and restart Xray.
+    pub fn apply_xray_template<F>(&self, change: F) -> Result<Option<String>>
+    where
+        F: FnOnce(&mut ConfigJson),
+    {
+        self.block_on(self.inner.apply_xray_template(change))
+    }
+
+    /// Get traffic per outbound, keyed by outbound tag.
+    pub fn get_outbounds_traffic(&self) -> Result<BTreeMap<String, OutboundTraffic>> {
+        self.block_on(self.inner.get_outbounds_traffic())
+    }
+
+    /// Reset the traffic counters of one outbound.
+    pub fn reset_outbound_traffic(&self, tag: &str) -> Result<()> {
+        self.block_on(self.inner.reset_outbound_traffic(tag))
+    }
+
+    /// Reset the traffic counters of every outbound.
+    pub fn reset_all_outbounds_traffic(&self) -> Result<()> {
+        self.block_on(self.inner.reset_all_outbounds_traffic())
+    }
+
+    /// Get the stored WARP account credentials.
+    pub fn get_warp_data(&self) -> Result<Option<WarpData>> {
+        self.block_on(self.inner.get_warp_data())
+    }
+
+    /// Forget the stored WARP account.
+    pub fn del_warp_data(&self) -> Result<()> {
+        self.block_on(self.inner.del_warp_data())
+    }
+
+    /// Fetch the registered WARP device and its WireGuard config.
+    pub fn get_warp_config(&self) -> Result<WarpDevice> {
+        self.block_on(self.inner.get_warp_config())
+    }
+
+    /// Register a new WARP device for a WireGuard key pair.
+    pub fn reg_warp(&self, private_key: &str, public_key: &str) -> Result<WarpRegistration> {
+        self.block_on(self.inner.reg_warp(private_key, public_key))
+    }
+
+    /// Attach a WARP+ license key to the registered account.
+    pub fn set_warp_license(&self, license: &str) -> Result<WarpData> {
+        self.block_on(self.inner.set_warp_license(license))
+    }
+
+    /// Install Xray version.
+    pub fn install_xray_version(&self, version: &str) -> Result<Option<()>> {
+        self.block_on(self.inner.install_xray_version(version))
+    }
+
+    /// Update geofile bundle.
+    pub fn update_geofile(&self) -> Result<Option<()>> {
+        self.block_on(self.inner.update_geofile())
+    }
+
+    /// Update geofile by name.
+    pub fn update_geofile_by_name(&self, file_name: &str) -> Result<Option<()>> {
+        self.block_on(self.inner.update_geofile_by_name(file_name))
+    }
+
+    /// Fetch server logs.
+    pub fn logs(&self, count: u32) -> Result<Vec<String>> {
+        self.block_on(self.inner.logs(count))
+    }
+
+    /// Fetch Xray logs.
+    pub fn xray_logs(&self, count: u32) -> Result<Option<Vec<String>>> {
+        self.block_on(self.inner.xray_logs(count))
+    }
+
+    /// Import DB upload.
+    pub fn import_db_upload(&self, filename: &str, bytes: Vec<u8>) -> Result<String> {
+        self.block_on(self.inner.import_db_upload(filename, bytes))
+    }
+
+    fn block_on<T>(&self, fut: impl Future<Output = Result<T>>) -> Result<T> {
+        self.runtime.block_on(fut)
+    }
+}
+
+fn runtime() -> Result<Runtime> {
+    Builder::new_multi_thread()
+        .worker_threads(1)
+        .thread_name("rustix3-blocking")
+        .enable_all()
+        .build()
+        .map_err(|e| Error::OtherError(format!("failed to start runtime: {e}")))
+}
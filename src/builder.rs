@@ -0,0 +1,204 @@
+use crate::Result;
+use crate::client::{AuthToken, Client, ClientOptions};
+use crate::credentials::{CredentialProvider, Credentials, SecretString, TokenProvider};
+use crate::error::Error;
+use crate::limits::{CircuitBreaker, RateLimit};
+use crate::middleware::{Layer, Middleware};
+use crate::proxy::ProxyOptions;
+use crate::retry::{Jitter, RetryPolicy};
+use crate::session::SessionStore;
+use crate::telemetry::EmailField;
+use crate::tls::TlsOptions;
+use crate::two_factor::TwoFactor;
+use crate::version::PanelVersion;
+use reqwest::{IntoUrl, Method, Url};
+use std::sync::Arc;
+use tokio::time::Duration;
+
+/// Builder for [`Client`] that does not touch the network.
+///
+/// With credentials the client logs in on its first call, or explicitly via [`Client::login`].
+#[derive(Debug)]
+pub struct ClientBuilder {
+    url: Result<Url>,
+    credentials: Option<Arc<dyn CredentialProvider>>,
+    token: Option<AuthToken>,
+    options: ClientOptions,
+}
+
+impl ClientBuilder {
+    pub fn new(url: impl IntoUrl) -> Self {
+        Self {
+            url: url.into_url().map_err(|e| Error::InvalidUrl(e.to_string())),
+            credentials: None,
+            token: None,
+            options: ClientOptions::default(),
+        }
+    }
+
+    /// Authenticate with a panel username and password.
+    pub fn credentials(
+        mut self,
+        username: impl Into<String>,
+        password: impl Into<SecretString>,
+    ) -> Self {
+        self.credentials = Some(Arc::new(Credentials::new(username, password)));
+        self
+    }
+
+    /// Fetch credentials from a provider on every login (env vars, secret files, vaults).
+    pub fn credential_provider(mut self, provider: Arc<dyn CredentialProvider>) -> Self {
+        self.credentials = Some(provider);
+        self
+    }
+
+    /// Authenticate with an existing bearer token.
+    pub fn token(mut self, token: AuthToken) -> Self {
+        self.token = Some(token);
+        self
+    }
+
+    /// Fetch a new bearer token before the current one expires, for clients without credentials.
+    pub fn token_provider(mut self, provider: Arc<dyn TokenProvider>) -> Self {
+        self.options.token_provider = Some(provider);
+        self
+    }
+
+    /// Replace all options at once.
+    pub fn options(mut self, options: ClientOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn retry_count(mut self, retry_count: u32) -> Self {
+        self.options.retry_count = retry_count;
+        self
+    }
+
+    pub fn retry_base_delay(mut self, delay: Duration) -> Self {
+        self.options.retry_base_delay = delay;
+        self
+    }
+
+    pub fn retry_max_delay(mut self, delay: Duration) -> Self {
+        self.options.retry_max_delay = delay;
+        self
+    }
+
+    pub fn retry_methods(mut self, methods: Vec<Method>) -> Self {
+        self.options.retry_methods = methods;
+        self
+    }
+
+    /// Retry `endpoint` (a client method name such as `online_clients`) regardless of its method.
+    pub fn retry_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.options.retry_endpoints.push(endpoint.into());
+        self
+    }
+
+    pub fn retry_jitter(mut self, jitter: Jitter) -> Self {
+        self.options.retry_jitter = jitter;
+        self
+    }
+
+    /// Use a custom retry policy instead of the `retry_*` options.
+    pub fn retry_policy(mut self, policy: Arc<dyn RetryPolicy>) -> Self {
+        self.options.retry_policy = Some(policy);
+        self
+    }
+
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.options.connect_timeout = timeout;
+        self
+    }
+
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.options.request_timeout = timeout;
+        self
+    }
+
+    pub fn two_factor(mut self, two_factor: TwoFactor) -> Self {
+        self.options.two_factor = Some(two_factor);
+        self
+    }
+
+    pub fn token_refresh_margin(mut self, margin: Duration) -> Self {
+        self.options.token_refresh_margin = margin;
+        self
+    }
+
+    pub fn web_base_path(mut self, path: impl Into<String>) -> Self {
+        self.options.web_base_path = Some(path.into());
+        self
+    }
+
+    pub fn tls(mut self, tls: TlsOptions) -> Self {
+        self.options.tls = tls;
+        self
+    }
+
+    pub fn proxy(mut self, proxy: ProxyOptions) -> Self {
+        self.options.proxy = Some(proxy);
+        self
+    }
+
+    pub fn session_store(mut self, store: Arc<dyn SessionStore>) -> Self {
+        self.options.session_store = Some(store);
+        self
+    }
+
+    /// Limit the number of requests in flight at once.
+    pub fn max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.options.max_in_flight = Some(max_in_flight);
+        self
+    }
+
+    /// Throttle outgoing requests to `requests_per_second`, allowing bursts of `burst`.
+    pub fn rate_limit(mut self, requests_per_second: f64, burst: u32) -> Self {
+        self.options.rate_limit = Some(RateLimit::new(requests_per_second, burst));
+        self
+    }
+
+    pub fn circuit_breaker(mut self, breaker: CircuitBreaker) -> Self {
+        self.options.circuit_breaker = Some(breaker);
+        self
+    }
+
+    /// Replace the whole middleware stack, outermost first.
+    pub fn layers(mut self, layers: Vec<Layer>) -> Self {
+        self.options.layers = layers;
+        self
+    }
+
+    /// Append a middleware at the innermost position, where it sees every retry attempt.
+    pub fn layer(mut self, middleware: impl Middleware + 'static) -> Self {
+        self.options.layers.push(Layer::custom(middleware));
+        self
+    }
+
+    /// Choose how client emails appear in tracing spans.
+    pub fn trace_emails(mut self, emails: EmailField) -> Self {
+        self.options.trace_emails = emails;
+        self
+    }
+
+    /// Assume this panel version instead of detecting it.
+    pub fn panel_version(mut self, version: PanelVersion) -> Self {
+        self.options.panel_version = Some(version);
+        self
+    }
+
+    /// Build an unauthenticated client without sending any request.
+    pub fn build(self) -> Result<Client> {
+        Client::build(self.credentials, self.token, self.url?, self.options)
+    }
+
+    /// Build the client and log in immediately.
+    pub async fn connect(self) -> Result<Client> {
+        let client = self.build()?;
+        if client.has_credentials() {
+            client.login().await?;
+        }
+        Ok(client)
+    }
+}
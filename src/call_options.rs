@@ -0,0 +1,91 @@
+use crate::Result;
+use crate::error::Error;
+use std::future::Future;
+use tokio::time::{Duration, Instant, timeout_at};
+pub use tokio_util::sync::CancellationToken;
+
+tokio::task_local! {
+    static CURRENT: CallOptions;
+}
+
+/// Overrides for the calls made inside [`CallOptions::scope`].
+///
+/// Unset fields fall back to the client's [`ClientOptions`](crate::ClientOptions).
+#[derive(Debug, Clone, Default)]
+pub struct CallOptions {
+    /// Timeout for each attempt.
+    pub timeout: Option<Duration>,
+    /// Maximum number of retries after the first attempt.
+    pub retry_count: Option<u32>,
+    /// Point in time by which each call must finish, retries and re-login included.
+    pub deadline: Option<Instant>,
+    pub cancel: Option<CancellationToken>,
+}
+
+impl CallOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn retry_count(mut self, retry_count: u32) -> Self {
+        self.retry_count = Some(retry_count);
+        self
+    }
+
+    pub fn deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Set the deadline to `budget` from now.
+    pub fn budget(self, budget: Duration) -> Self {
+        self.deadline(Instant::now() + budget)
+    }
+
+    /// Abort calls with [`Error::Cancelled`] once `token` is cancelled.
+    pub fn cancel_on(mut self, token: CancellationToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    /// Run `fut` with these overrides applied to every client call it makes.
+    pub async fn scope<F: Future>(self, fut: F) -> F::Output {
+        CURRENT.scope(self, fut).await
+    }
+
+    pub(crate) fn current() -> Self {
+        CURRENT.try_with(Clone::clone).unwrap_or_default()
+    }
+
+    /// Time left before the deadline, if one is set.
+    pub(crate) fn remaining(&self) -> Option<Duration> {
+        self.deadline
+            .map(|d| d.saturating_duration_since(Instant::now()))
+    }
+
+    /// Apply the deadline and cancellation token to a whole call.
+    pub(crate) async fn guard<T>(&self, fut: impl Future<Output = Result<T>>) -> Result<T> {
+        // Boxed so the wrappers below do not each hold a copy of a large call future.
+        let fut = Box::pin(fut);
+        let fut = async {
+            match self.deadline {
+                Some(deadline) => timeout_at(deadline, fut)
+                    .await
+                    .unwrap_or(Err(Error::DeadlineExceeded)),
+                None => fut.await,
+            }
+        };
+        match &self.cancel {
+            Some(token) => token
+                .run_until_cancelled(fut)
+                .await
+                .unwrap_or(Err(Error::Cancelled)),
+            None => fut.await,
+        }
+    }
+}
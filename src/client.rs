@@ -1,34 +1,81 @@
 #![allow(dead_code)]
 
-use super::{
-    ClientIpsResponse, ClientsStatsResponse, ClientsStatsVecResponse, ConfigJsonResponse,
-    CpuHistoryResponse, DeleteInboundResponse, EchCertResponse, InboundResponse, InboundsResponse,
-    LoginResponse, Mldsa65Response, Mlkem768Response, NullObjectResponse, OnlineClientsResponse,
-    OptStringVecResponse, Result, ServerStatusResponse, StringResponse, StringVecResponse,
-    UuidResponse, VlessEncResponse, X25519CertResponse,
-};
+use super::{BoolResponse, ClientIpsResponse, JsonResponse, LoginResponse, Result};
+use crate::batch::{self, BatchMode, DEFAULT_BATCH_CONCURRENCY};
+use crate::builder::ClientBuilder;
+use crate::call_options::CallOptions;
+use crate::credentials::{CredentialProvider, Credentials, SecretString, TokenProvider};
 use crate::error::Error;
+use crate::limits::{Breaker, CircuitBreaker, RateLimit, TokenBucket};
+use crate::middleware::{Layer, Next};
 use crate::models::{
-    ClientRequest, ClientStats, ConfigJson, CpuHistoryPoint, CreateInboundRequest, EchCert,
-    Inbounds, LoginInfo, Mldsa65, Mlkem768, ServerStatus, Uuid, VlessEnc, X25519Cert,
+    AllSetting, ClientRequest, ClientStats, ConfigJson, CpuHistoryPoint, CreateInboundRequest,
+    EchCert, Inbounds, LoginInfo, Mldsa65, Mlkem768, OutboundTraffic, Response, ServerStatus, Uuid,
+    VlessEnc, WarpData, WarpDevice, WarpRegistration, X25519Cert, XraySetting, from_unix_millis,
 };
-use crate::response_ext::ResponseJsonVerboseExt;
+use crate::proxy::{self, ProxyOptions};
+use crate::response_ext::{JsonVerboseError, ResponseJsonVerboseExt};
+use crate::retry::{Endpoint, ExponentialBackoff, Jitter, Outcome, RetryPolicy};
+use crate::session::{SessionStore, StoredSession, unix_now, unix_secs};
+use crate::telemetry::{self, Call, EmailField};
+use crate::tls::{self, TlsOptions};
+use crate::two_factor::TwoFactor;
+use crate::version::{Capabilities, PanelVersion, version_from_html};
+use futures::stream::BoxStream;
 use log::debug;
-use reqwest::header::RETRY_AFTER;
+use reqwest::cookie::{CookieStore, Jar};
+use reqwest::header::{AUTHORIZATION, CONTENT_TYPE, HeaderValue, SET_COOKIE};
 use reqwest::multipart::{Form, Part};
-use reqwest::{Client as RClient, IntoUrl, Method, StatusCode, Url};
-use serde::Serialize;
+use reqwest::{Client as RClient, IntoUrl, Method, Request, StatusCode, Url};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::SystemTime;
+use tokio::sync::{Mutex, OnceCell, Semaphore};
 use tokio::time::{Duration, sleep};
 
-/// Client configuration for retry policy and timeouts.
+/// Tag the panel treats as "every outbound" when resetting traffic.
+const ALL_OUTBOUND_TAGS: &str = "-alltags-";
+
+/// Client configuration for retry policy, timeouts, authentication and transport.
 #[derive(Debug, Clone)]
 pub struct ClientOptions {
     pub retry_count: u32,
     pub retry_base_delay: Duration,
     pub retry_max_delay: Duration,
     pub retry_methods: Vec<Method>,
+    /// Endpoints retried regardless of method, e.g. read-only POSTs like `online_clients`.
+    pub retry_endpoints: Vec<String>,
+    pub retry_jitter: Jitter,
+    /// Custom retry policy; when unset, [`ExponentialBackoff`] is built from the `retry_*` options.
+    pub retry_policy: Option<Arc<dyn RetryPolicy>>,
     pub connect_timeout: Duration,
     pub request_timeout: Duration,
+    pub two_factor: Option<TwoFactor>,
+    /// Refresh the bearer token this long before it expires.
+    pub token_refresh_margin: Duration,
+    /// Fetch a new bearer token when a client without credentials nears `expires_at`.
+    pub token_provider: Option<Arc<dyn TokenProvider>>,
+    /// Secret web base path the panel is served under, e.g. `/AbCdEf/`.
+    pub web_base_path: Option<String>,
+    pub tls: TlsOptions,
+    pub proxy: Option<ProxyOptions>,
+    /// Persist the session so later processes can skip the login.
+    pub session_store: Option<Arc<dyn SessionStore>>,
+    /// Middleware stack every request passes through, outermost first.
+    pub layers: Vec<Layer>,
+    /// How client emails appear in tracing spans (`tracing` feature).
+    pub trace_emails: EmailField,
+    /// Maximum number of requests in flight at once, retries included.
+    pub max_in_flight: Option<usize>,
+    pub rate_limit: Option<RateLimit>,
+    pub circuit_breaker: Option<CircuitBreaker>,
+    /// Skip version detection and assume this panel version.
+    pub panel_version: Option<PanelVersion>,
 }
 
 impl Default for ClientOptions {
@@ -38,8 +85,24 @@ impl Default for ClientOptions {
             retry_base_delay: Duration::from_millis(200),
             retry_max_delay: Duration::from_secs(2),
             retry_methods: vec![Method::GET, Method::HEAD],
+            retry_endpoints: Vec::new(),
+            retry_jitter: Jitter::None,
+            retry_policy: None,
             connect_timeout: Duration::from_secs(5),
             request_timeout: Duration::from_secs(30),
+            two_factor: None,
+            token_refresh_margin: Duration::from_secs(60),
+            token_provider: None,
+            web_base_path: None,
+            tls: TlsOptions::default(),
+            proxy: None,
+            session_store: None,
+            layers: Layer::default_stack(),
+            trace_emails: EmailField::default(),
+            max_in_flight: None,
+            rate_limit: None,
+            circuit_breaker: None,
+            panel_version: None,
         }
     }
 }
@@ -51,14 +114,82 @@ pub struct LoginResult {
     pub details: Option<LoginInfo>,
 }
 
+/// Bearer token sent as `Authorization` header.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuthToken {
+    pub token: String,
+    /// Unix timestamp (seconds or milliseconds) after which the panel rejects the token.
+    pub expires_at: Option<i64>,
+}
+
+impl AuthToken {
+    pub fn new(token: impl Into<String>) -> Self {
+        Self {
+            token: token.into(),
+            expires_at: None,
+        }
+    }
+
+    pub fn with_expires_at(mut self, expires_at: i64) -> Self {
+        self.expires_at = Some(expires_at);
+        self
+    }
+
+    /// Whether the token expires within `margin` from now.
+    pub fn expires_within(&self, margin: Duration) -> bool {
+        let Some(expires_at) = self.expires_at else {
+            return false;
+        };
+        unix_now().saturating_add(margin.as_secs() as i64) >= unix_secs(expires_at)
+    }
+}
+
+impl fmt::Debug for AuthToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuthToken")
+            .field("token", &"<redacted>")
+            .field("expires_at", &self.expires_at)
+            .finish()
+    }
+}
+
+/// Builds a fresh request when the first one cannot be cloned for a replay.
+type Rebuild<'a> = &'a (dyn Fn() -> reqwest::RequestBuilder + Send + Sync);
+
 /// API client for 3x-ui panel.
-#[derive(Debug)]
 pub struct Client {
-    username: String,
-    password: String,
+    credentials: Option<Arc<dyn CredentialProvider>>,
     url: Url,
     client: RClient,
+    cookies: Arc<Jar>,
     options: ClientOptions,
+    token: RwLock<Option<AuthToken>>,
+    session_generation: AtomicU64,
+    relogin_lock: Mutex<()>,
+    in_flight: Option<Semaphore>,
+    rate_limiter: Option<TokenBucket>,
+    breaker: Option<Breaker>,
+    retry_policy: Arc<dyn RetryPolicy>,
+    capabilities: OnceCell<Capabilities>,
+}
+
+impl fmt::Debug for Client {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The cookie jar and token hold the live session, so only report whether one exists.
+        let has_token = self
+            .token
+            .read()
+            .map(|token| token.is_some())
+            .unwrap_or(false);
+        f.debug_struct("Client")
+            .field("credentials", &self.credentials)
+            .field("url", &self.url)
+            .field("options", &self.options)
+            .field("cookies", &"<redacted>")
+            .field("token", &has_token.then_some("<redacted>"))
+            .field("capabilities", &self.capabilities.get())
+            .finish_non_exhaustive()
+    }
 }
 
 impl Client {
@@ -78,30 +209,75 @@ impl Client {
         url: impl IntoUrl,
         options: ClientOptions,
     ) -> Result<Self> {
-        let client = Self {
-            username: username.into(),
-            password: password.into(),
-            url: url.into_url()?,
-            client: RClient::builder()
-                .cookie_store(true)
-                .connect_timeout(options.connect_timeout)
-                .timeout(options.request_timeout)
-                .build()?,
-            options,
-        };
+        let credentials = Credentials::new(username.into(), password.into());
+        let client = Self::build(Some(Arc::new(credentials)), None, url, options)?;
         debug!("{:?}", client);
-        let _ = client.login().await?;
+        client.ensure_logged_in().await?;
         Ok(client)
     }
 
+    /// Start configuring a client; nothing is sent until the first call or `login()`.
+    pub fn builder(url: impl IntoUrl) -> ClientBuilder {
+        ClientBuilder::new(url)
+    }
+
+    /// Create a client authenticated by an existing bearer token, without logging in.
+    pub fn from_token(token: AuthToken, url: impl IntoUrl, options: ClientOptions) -> Result<Self> {
+        Self::build(None, Some(token), url, options)
+    }
+
+    pub(crate) fn build(
+        credentials: Option<Arc<dyn CredentialProvider>>,
+        token: Option<AuthToken>,
+        url: impl IntoUrl,
+        options: ClientOptions,
+    ) -> Result<Self> {
+        let url = url
+            .into_url()
+            .map_err(|e| Error::InvalidUrl(e.to_string()))?;
+        let url = normalize_base_url(url, options.web_base_path.as_deref())?;
+        let cookies = Arc::new(Jar::default());
+        Ok(Self {
+            credentials,
+            url,
+            client: build_http_client(&options, cookies.clone())?,
+            cookies,
+            in_flight: options.max_in_flight.map(|n| Semaphore::new(n.max(1))),
+            rate_limiter: options.rate_limit.map(TokenBucket::new),
+            breaker: options.circuit_breaker.map(Breaker::new),
+            retry_policy: options
+                .retry_policy
+                .clone()
+                .unwrap_or_else(|| Arc::new(ExponentialBackoff::from_options(&options))),
+            capabilities: OnceCell::new(),
+            options,
+            token: RwLock::new(token),
+            session_generation: AtomicU64::new(0),
+            relogin_lock: Mutex::new(()),
+        })
+    }
+
+    pub(crate) fn has_credentials(&self) -> bool {
+        self.credentials.is_some()
+    }
+
+    /// Current bearer token, if the panel issued one or the client was built from one.
+    pub fn token(&self) -> Option<AuthToken> {
+        self.token.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Replace the bearer token used for subsequent calls.
+    pub fn set_token(&self, token: Option<AuthToken>) {
+        *self.token.write().unwrap_or_else(|e| e.into_inner()) = token;
+    }
+
     fn gen_url_with_base(&self, base: &[&str], segs: Vec<&str>) -> Result<Url> {
-        let base_str = self.url.as_str().trim_end_matches('/');
-        let mut url =
-            Url::parse(base_str).map_err(|_| Error::InvalidUrl("Invalid base URL".into()))?;
+        let mut url = self.url.clone();
         {
             let mut path_segments = url
                 .path_segments_mut()
                 .map_err(|_| Error::InvalidUrl("Cannot be a base URL".into()))?;
+            path_segments.pop_if_empty();
             path_segments.extend(base.iter().copied());
             path_segments.extend(segs);
         }
@@ -118,31 +294,78 @@ impl Client {
         self.gen_url_with_base(&base_segs, segs)
     }
 
-    async fn login(&self) -> Result<LoginResult> {
+    fn gen_setting_url(&self, segs: Vec<&str>) -> Result<Url> {
+        self.gen_url_with_base(&["panel", "setting"], segs)
+    }
+
+    fn gen_xray_url(&self, segs: Vec<&str>) -> Result<Url> {
+        self.gen_url_with_base(&["panel", "xray"], segs)
+    }
+
+    /// Log in with the configured credentials, replacing any current session.
+    pub async fn login(&self) -> Result<LoginResult> {
+        let overrides = CallOptions::current();
+        let login = overrides.guard(async {
+            let _guard = self.relogin_lock.lock().await;
+            let result = self.do_login().await?;
+            self.session_generation.fetch_add(1, Ordering::AcqRel);
+            Ok(result)
+        });
+        telemetry::traced(Call::new("login"), self.options.trace_emails, login).await
+    }
+
+    async fn ensure_logged_in(&self) -> Result<()> {
+        if self.credentials.is_none() || self.session_generation.load(Ordering::Acquire) != 0 {
+            return Ok(());
+        }
+        let _guard = self.relogin_lock.lock().await;
+        if self.session_generation.load(Ordering::Acquire) != 0 {
+            return Ok(());
+        }
+        if self.restore_session().await {
+            debug!("Reusing stored panel session");
+        } else {
+            debug!("Logging in on first use");
+            self.do_login().await?;
+        }
+        self.session_generation.fetch_add(1, Ordering::AcqRel);
+        Ok(())
+    }
+
+    async fn do_login(&self) -> Result<LoginResult> {
+        let Some(provider) = &self.credentials else {
+            return Err(Error::OtherError(
+                "no credentials configured to log in".into(),
+            ));
+        };
+        let credentials = provider.credentials().await?;
         #[derive(Serialize)]
-        struct LoginRequest {
-            username: String,
-            password: String,
+        struct LoginRequest<'a> {
+            username: &'a str,
+            password: &'a str,
+            #[serde(rename = "twoFactorCode")]
+            two_factor_code: &'a str,
         }
+        let two_factor_code = match &self.options.two_factor {
+            Some(two_factor) => two_factor.code()?,
+            None => SecretString::new(""),
+        };
         let body = LoginRequest {
-            username: self.username.clone(),
-            password: self.password.clone(),
+            username: &credentials.username,
+            password: credentials.password.expose_secret(),
+            two_factor_code: two_factor_code.expose_secret(),
         };
 
         debug!("Sending login request!");
-        let json_url = self.url.clone().join("login").unwrap();
+        let json_url = self.gen_url_with_base(&[], vec!["login"])?;
         let response = self
-            .send_with_retry(self.client.post(json_url).json(&body))
+            .dispatch("login", self.client.post(json_url).json(&body))
             .await?;
         match response.status() {
             StatusCode::NOT_FOUND | StatusCode::UNSUPPORTED_MEDIA_TYPE => {
-                let form_url = self.url.clone().join("login/").unwrap();
-                let form = Form::new()
-                    .text("username", self.username.clone())
-                    .text("password", self.password.clone())
-                    .text("twoFactorCode", String::new());
+                let form_url = self.gen_url_with_base(&[], vec!["login", ""])?;
                 let form_response = self
-                    .send_with_retry(self.client.post(form_url).multipart(form))
+                    .dispatch("login", self.client.post(form_url).form(&body))
                     .await?;
                 match form_response.status() {
                     StatusCode::NOT_FOUND => {
@@ -155,54 +378,218 @@ impl Client {
                         log::warn!("Unimplemented handle err{:?}", e)
                     }
                 }
+                let cookie_expires_at = cookie_expires_at(&form_response);
                 let login: LoginResponse = form_response.json().await?;
-                let message = login.message.clone();
-                let details = login.into_result()?;
-                return Ok(LoginResult { message, details });
+                return self.finish_login(login, cookie_expires_at).await;
             }
             StatusCode::OK => {}
             e => {
                 log::warn!("Unimplemented handle err{:?}", e)
             }
         }
+        let cookie_expires_at = cookie_expires_at(&response);
         let login: LoginResponse = response.json().await?;
+        self.finish_login(login, cookie_expires_at).await
+    }
+
+    async fn finish_login(
+        &self,
+        login: LoginResponse,
+        cookie_expires_at: Option<i64>,
+    ) -> Result<LoginResult> {
+        if login.is_err() && self.options.two_factor.is_none() {
+            let flagged = login
+                .object
+                .as_ref()
+                .and_then(|info| info.two_factor_enabled)
+                .unwrap_or(false);
+            if flagged || self.two_factor_enabled().await.unwrap_or(false) {
+                return Err(Error::TwoFactorRequired);
+            }
+        }
         let message = login.message.clone();
-        let details = login.into_result()?;
+        let details = login.into_result_for("login", StatusCode::OK)?;
+        if let Some(token) = details.as_ref().and_then(|info| info.token.clone()) {
+            let expires_at = details.as_ref().and_then(|info| info.expires_at);
+            self.set_token(Some(AuthToken { token, expires_at }));
+        }
+        self.save_session(cookie_expires_at).await;
         Ok(LoginResult { message, details })
     }
 
-    /// List all inbounds.
-    pub async fn get_inbounds_list(&self) -> Result<Vec<Inbounds>> {
-        let path = vec!["list"];
-        let res: InboundsResponse = self
-            .send_with_retry(self.client.get(self.gen_inbounds_url(path)?))
+    async fn session_key(&self) -> Option<String> {
+        let credentials = self.credentials.as_ref()?.credentials().await.ok()?;
+        Some(format!("{}#{}", self.url, credentials.username))
+    }
+
+    async fn save_session(&self, cookie_expires_at: Option<i64>) {
+        let Some(store) = &self.options.session_store else {
+            return;
+        };
+        let Some(key) = self.session_key().await else {
+            return;
+        };
+        let token = self.token();
+        let token_expires_at = token.as_ref().and_then(|t| t.expires_at).map(unix_secs);
+        let session = StoredSession {
+            cookie: self
+                .cookies
+                .cookies(&self.url)
+                .and_then(|v| v.to_str().ok().map(str::to_string)),
+            token,
+            expires_at: match (cookie_expires_at, token_expires_at) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            },
+        };
+        if let Err(e) = store.save(&key, &session).await {
+            log::warn!("Failed to save panel session: {e}");
+        }
+    }
+
+    async fn restore_session(&self) -> bool {
+        let Some(store) = &self.options.session_store else {
+            return false;
+        };
+        let Some(key) = self.session_key().await else {
+            return false;
+        };
+        let session = match store.load(&key).await {
+            Ok(Some(session)) => session,
+            Ok(None) => return false,
+            Err(e) => {
+                log::warn!("Failed to load panel session: {e}");
+                return false;
+            }
+        };
+        if session.expires_within(self.options.token_refresh_margin) {
+            debug!("Stored panel session is stale");
+            return false;
+        }
+        if let Some(cookie) = &session.cookie {
+            for pair in cookie.split(';') {
+                self.cookies.add_cookie_str(pair.trim(), &self.url);
+            }
+        }
+        self.set_token(session.token.clone());
+        if self.validate_session().await {
+            return true;
+        }
+        debug!("Stored panel session was rejected");
+        self.set_token(None);
+        if let Err(e) = store.remove(&key).await {
+            log::warn!("Failed to remove panel session: {e}");
+        }
+        false
+    }
+
+    async fn validate_session(&self) -> bool {
+        let Ok(url) = self.gen_inbounds_url(vec!["onlines"]) else {
+            return false;
+        };
+        let Ok(response) = self
+            .dispatch("validate_session", self.client.post(url.clone()))
+            .await
+        else {
+            return false;
+        };
+        if self.is_session_expired(Some(&url), &response) {
+            return false;
+        }
+        response
+            .json_verbose::<JsonResponse>()
+            .await
+            .is_ok_and(|r| r.is_ok())
+    }
+
+    /// Version of the connected panel, detected once and cached; `None` if it cannot be told.
+    pub async fn panel_version(&self) -> Result<Option<PanelVersion>> {
+        Ok(self.capabilities().await?.version)
+    }
+
+    /// Endpoints the connected panel supports, probed once and cached.
+    pub async fn capabilities(&self) -> Result<Capabilities> {
+        self.capabilities
+            .get_or_try_init(|| async {
+                if let Some(version) = self.options.panel_version {
+                    return Ok(Capabilities {
+                        version: Some(version),
+                    });
+                }
+                let response = self
+                    .dispatch("panel_version", self.client.get(self.url.clone()))
+                    .await?;
+                let version = version_from_html(&response.text().await?);
+                debug!("Detected panel version: {:?}", version);
+                Ok(Capabilities { version })
+            })
+            .await
+            .copied()
+    }
+
+    async fn two_factor_enabled(&self) -> Result<bool> {
+        let url = self.gen_url_with_base(&[], vec!["getTwoFactorEnable"])?;
+        let res: BoolResponse = self
+            .dispatch("two_factor_enabled", self.client.post(url))
             .await?
             .json_verbose()
             .await?;
         res.into_result()
     }
 
+    /// List all inbounds.
+    pub async fn get_inbounds_list(&self) -> Result<Vec<Inbounds>> {
+        let path = vec!["list"];
+        self.fetch(
+            Call::new("get_inbounds_list"),
+            self.client.get(self.gen_inbounds_url(path)?),
+        )
+        .await
+    }
+
     /// Get inbound by id.
     pub async fn get_inbound_by_id(&self, inbound_id: u64) -> Result<Inbounds> {
         let id = inbound_id.to_string();
         let path = vec!["get", &id];
-        let res: InboundResponse = self
-            .send_with_retry(self.client.get(self.gen_inbounds_url(path)?))
-            .await?
-            .json_verbose()
-            .await?;
-        res.into_result()
+        self.fetch(
+            Call::new("get_inbound_by_id").inbound(inbound_id),
+            self.client.get(self.gen_inbounds_url(path)?),
+        )
+        .await
     }
 
     /// Get client traffic by email.
     pub async fn get_client_traffic_by_email(&self, email: impl AsRef<str>) -> Result<ClientStats> {
         let path = vec!["getClientTraffics", email.as_ref()];
-        let res: ClientsStatsResponse = self
-            .send_with_retry(self.client.get(self.gen_inbounds_url(path)?))
-            .await?
-            .json_verbose()
-            .await?; // todo check is null return user not found
-        res.into_result()
+        let stats: Option<ClientStats> = self
+            .fetch(
+                Call::new("get_client_traffic_by_email").email(email.as_ref()),
+                self.client.get(self.gen_inbounds_url(path)?),
+            )
+            .await?;
+        stats.ok_or_else(|| Error::ClientNotFound {
+            endpoint: "get_client_traffic_by_email",
+            message: format!("no client with email `{}`", email.as_ref()),
+        })
+    }
+
+    /// Get traffic for many emails, at most `max_in_flight` at once, in completion order.
+    pub fn get_client_traffic_batch<I>(
+        &self,
+        emails: I,
+        mode: BatchMode,
+    ) -> BoxStream<'_, (String, Result<ClientStats>)>
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        let emails = emails.into_iter().map(Into::into).collect();
+        batch::run(
+            emails,
+            self.batch_concurrency(),
+            mode,
+            move |email| async move { self.get_client_traffic_by_email(email).await },
+        )
     }
 
     /// Get client traffic by id.
@@ -210,57 +597,87 @@ impl Client {
         // todo id to uuid
         let id = id.as_ref();
         let path = vec!["getClientTrafficsById", id];
-        let res: ClientsStatsVecResponse = self
-            .send_with_retry(self.client.get(self.gen_inbounds_url(path)?))
-            .await?
-            .json_verbose()
-            .await?;
-        res.into_result()
+        self.fetch(
+            Call::new("get_client_traffic_by_id"),
+            self.client.get(self.gen_inbounds_url(path)?),
+        )
+        .await
     }
 
     /// Trigger backup via bot.
     pub async fn send_backup_by_bot(&self) -> Result<()> {
         // todo tests
         let path = vec!["createbackup"];
-        let res = self
-            .send_with_retry(self.client.get(self.gen_inbounds_url(path)?))
-            .await?;
-        if res.status() != StatusCode::OK {
-            return Err(Error::OtherError("Todo".into()));
-        }
-        Ok(())
+        self.send(
+            Call::new("send_backup_by_bot"),
+            self.client.get(self.gen_inbounds_url(path)?),
+            async |res| {
+                let status = res.status();
+                if status != StatusCode::OK {
+                    return Err(Error::HttpStatus {
+                        endpoint: "send_backup_by_bot",
+                        status,
+                        body: res.text().await.unwrap_or_default(),
+                    });
+                }
+                Ok(())
+            },
+        )
+        .await
     }
 
     /// Get client IPs by email.
     pub async fn get_client_ips(&self, client_email: impl AsRef<str>) -> Result<ClientIpsResponse> {
         // todo tests
         let path = vec!["clientIps", client_email.as_ref()];
-        let res = self
-            .send_with_retry(self.client.post(self.gen_inbounds_url(path)?))
-            .await?;
-        res.json_verbose().await.map_err(Into::into)
+        self.send(
+            Call::new("get_client_ips").email(client_email.as_ref()),
+            self.client.post(self.gen_inbounds_url(path)?),
+            async |res| res.json_verbose().await.map_err(Into::into),
+        )
+        .await
+    }
+
+    /// Get IPs for many emails, at most `max_in_flight` at once, in completion order.
+    pub fn get_client_ips_batch<I>(
+        &self,
+        emails: I,
+        mode: BatchMode,
+    ) -> BoxStream<'_, (String, Result<ClientIpsResponse>)>
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        let emails = emails.into_iter().map(Into::into).collect();
+        batch::run(
+            emails,
+            self.batch_concurrency(),
+            mode,
+            move |email| async move { self.get_client_ips(email).await },
+        )
+    }
+
+    fn batch_concurrency(&self) -> usize {
+        self.options
+            .max_in_flight
+            .unwrap_or(DEFAULT_BATCH_CONCURRENCY)
     }
 
     /// Create inbound.
     pub async fn add_inbound(&self, req: &CreateInboundRequest) -> Result<Inbounds> {
         let url = self.gen_inbounds_url(vec!["add"])?;
-        let res: InboundResponse = self
-            .send_with_retry(self.client.post(url).json(req))
-            .await?
-            .json_verbose()
-            .await?;
-        res.into_result()
+        self.fetch(Call::new("add_inbound"), self.client.post(url).json(req))
+            .await
     }
 
     /// Add client(s) to inbound.
     pub async fn add_client_to_inbound(&self, req: &ClientRequest) -> Result<Option<()>> {
         let url = self.gen_inbounds_url(vec!["addClient"])?;
-        let res: NullObjectResponse = self
-            .send_with_retry(self.client.post(url).json(req))
-            .await?
-            .json_verbose()
-            .await?;
-        res.into_result()
+        self.fetch(
+            Call::new("add_client_to_inbound").inbound(req.id),
+            self.client.post(url).json(req),
+        )
+        .await
     }
 
     /// Update inbound.
@@ -270,112 +687,128 @@ impl Client {
         req: &CreateInboundRequest,
     ) -> Result<Inbounds> {
         let url = self.gen_inbounds_url(vec!["update", &inbound_id.to_string()])?;
-        let res: InboundResponse = self
-            .send_with_retry(self.client.post(url).json(req))
-            .await?
-            .json_verbose()
-            .await?;
-        res.into_result()
+        self.fetch(
+            Call::new("update_inbound").inbound(inbound_id),
+            self.client.post(url).json(req),
+        )
+        .await
     }
 
     /// Update client by UUID.
     pub async fn update_client(&self, uuid: &str, req: &ClientRequest) -> Result<Option<()>> {
         let url = self.gen_inbounds_url(vec!["updateClient", uuid])?;
-        let res: NullObjectResponse = self
-            .send_with_retry(self.client.post(url).json(req))
-            .await?
-            .json_verbose()
-            .await?;
-        res.into_result()
+        self.fetch(
+            Call::new("update_client").inbound(req.id),
+            self.client.post(url).json(req),
+        )
+        .await
     }
 
     /// Clear client IPs by email.
     pub async fn clear_client_ips(&self, email: &str) -> Result<Option<()>> {
         let url = self.gen_inbounds_url(vec!["clearClientIps", email])?;
-        let res: NullObjectResponse = self
-            .send_with_retry(self.client.post(url))
-            .await?
-            .json_verbose()
-            .await?;
-        res.into_result()
+        self.fetch(
+            Call::new("clear_client_ips").email(email),
+            self.client.post(url),
+        )
+        .await
     }
 
     /// Reset all inbound traffics.
     pub async fn reset_all_inbound_traffics(&self) -> Result<Option<()>> {
         let url = self.gen_inbounds_url(vec!["resetAllTraffics"])?;
-        let res: NullObjectResponse = self
-            .send_with_retry(self.client.post(url))
-            .await?
-            .json_verbose()
-            .await?;
-        res.into_result()
+        self.fetch(
+            Call::new("reset_all_inbound_traffics"),
+            self.client.post(url),
+        )
+        .await
     }
 
     /// Reset all client traffics for inbound.
     pub async fn reset_all_client_traffics(&self, inbound_id: u64) -> Result<Option<()>> {
         let url = self.gen_inbounds_url(vec!["resetAllClientTraffics", &inbound_id.to_string()])?;
-        let res: NullObjectResponse = self
-            .send_with_retry(self.client.post(url))
-            .await?
-            .json_verbose()
-            .await?;
-        res.into_result()
+        self.fetch(
+            Call::new("reset_all_client_traffics").inbound(inbound_id),
+            self.client.post(url),
+        )
+        .await
     }
 
     /// Reset client traffic by email.
     pub async fn reset_client_traffic(&self, inbound_id: u64, email: &str) -> Result<Option<()>> {
         let url =
             self.gen_inbounds_url(vec![&inbound_id.to_string(), "resetClientTraffic", email])?;
-        let res: NullObjectResponse = self
-            .send_with_retry(self.client.post(url))
-            .await?
-            .json_verbose()
+        self.fetch(
+            Call::new("reset_client_traffic")
+                .inbound(inbound_id)
+                .email(email),
+            self.client.post(url),
+        )
+        .await
+    }
+
+    /// Set a client's upload and download counters, e.g. to carry usage over from another server.
+    ///
+    /// Fails with [`Error::ClientNotFound`] if no client has `email`, since the panel itself
+    /// silently ignores unknown emails.
+    pub async fn update_client_traffic(&self, email: &str, up: u128, down: u128) -> Result<()> {
+        let upload = panel_counter("up", up)?;
+        let download = panel_counter("down", down)?;
+        if let Err(err) = self.get_client_traffic_by_email(email).await {
+            return Err(match err {
+                Error::ClientNotFound { message, .. } => Error::ClientNotFound {
+                    endpoint: "update_client_traffic",
+                    message,
+                },
+                err => err,
+            });
+        }
+        let url = self.gen_inbounds_url(vec!["updateClientTraffic", email])?;
+        let body = serde_json::json!({ "upload": upload, "download": download });
+        let _: Value = self
+            .fetch(
+                Call::new("update_client_traffic").email(email),
+                self.client.post(url).json(&body),
+            )
             .await?;
-        res.into_result()
+        Ok(())
     }
 
     /// Delete client by UUID.
     pub async fn delete_client(&self, inbound_id: u64, uuid: &str) -> Result<Option<()>> {
         let url = self.gen_inbounds_url(vec![&inbound_id.to_string(), "delClient", uuid])?;
-        let res: NullObjectResponse = self
-            .send_with_retry(self.client.post(url))
-            .await?
-            .json_verbose()
-            .await?;
-        res.into_result()
+        self.fetch(
+            Call::new("delete_client").inbound(inbound_id),
+            self.client.post(url),
+        )
+        .await
     }
 
     /// Delete inbound by id.
     pub async fn delete_inbound(&self, inbound_id: u64) -> Result<u64> {
         let url = self.gen_inbounds_url(vec!["del", &inbound_id.to_string()])?;
-        let res: DeleteInboundResponse = self
-            .send_with_retry(self.client.post(url))
-            .await?
-            .json_verbose()
-            .await?;
-        res.into_result()
+        self.fetch(
+            Call::new("delete_inbound").inbound(inbound_id),
+            self.client.post(url),
+        )
+        .await
     }
 
     /// Delete depleted clients by inbound.
     pub async fn delete_depleted_clients(&self, inbound_id: u64) -> Result<Option<()>> {
         let url = self.gen_inbounds_url(vec!["delDepletedClients", &inbound_id.to_string()])?;
-        let res: NullObjectResponse = self
-            .send_with_retry(self.client.post(url))
-            .await?
-            .json_verbose()
-            .await?;
-        res.into_result()
+        self.fetch(
+            Call::new("delete_depleted_clients").inbound(inbound_id),
+            self.client.post(url),
+        )
+        .await
     }
 
     /// List online clients.
     pub async fn online_clients(&self) -> Result<Option<Vec<String>>> {
         let url = self.gen_inbounds_url(vec!["onlines"])?;
-        let res: OnlineClientsResponse = self
-            .send_with_retry(self.client.post(url))
-            .await?
-            .json_verbose()
-            .await?;
-        res.into_result()
+        self.fetch(Call::new("online_clients"), self.client.post(url))
+            .await
     }
 
     /// Import inbound.
@@ -383,323 +816,751 @@ impl Client {
         let url = self.gen_inbounds_url(vec!["import"])?;
         let json_str = serde_json::to_string(inbound)
             .map_err(|e| Error::OtherError(format!("serialize inbound: {e}")))?;
-        let form = Form::new().text("data", json_str);
-        let res: InboundResponse = self
-            .send_with_retry(self.client.post(url).multipart(form))
-            .await?
-            .json_verbose()
-            .await?;
-        res.into_result()
+        let build = || {
+            let form = Form::new().text("data", json_str.clone());
+            self.client.post(url.clone()).multipart(form)
+        };
+        self.fetch_rebuilt(Call::new("import_inbound"), &build)
+            .await
     }
 
-    /// Get last online clients.
-    pub async fn get_last_online(&self) -> Result<Option<Vec<String>>> {
-        let url = self.gen_inbounds_url(vec!["onlines"])?;
-        let res: OptStringVecResponse = self
-            .send_with_retry(self.client.post(url))
-            .await?
-            .json_verbose()
+    /// When each client was last seen, by email. Clients that never connected are left out.
+    pub async fn get_last_online(&self) -> Result<BTreeMap<String, SystemTime>> {
+        let url = self.gen_inbounds_url(vec!["lastOnline"])?;
+        let last_online: Option<BTreeMap<String, i64>> = self
+            .fetch(Call::new("get_last_online"), self.client.post(url))
             .await?;
-        res.into_result()
+        Ok(last_online
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(email, millis)| Some((email, from_unix_millis(millis)?)))
+            .collect())
+    }
+
+    /// [`get_last_online`](Self::get_last_online), completed with `ClientStats::last_online`
+    /// from the inbound list for clients the endpoint omits.
+    pub async fn get_last_online_merged(&self) -> Result<BTreeMap<String, SystemTime>> {
+        let mut last_online = self.get_last_online().await?;
+        for inbound in self.get_inbounds_list().await? {
+            for stats in inbound.client_stats.into_iter().flatten() {
+                if let Some(at) = stats.last_online_at() {
+                    last_online.entry(stats.email).or_insert(at);
+                }
+            }
+        }
+        Ok(last_online)
     }
 
     /// Delete client by email.
     pub async fn del_client_by_email(&self, inbound_id: u64, email: &str) -> Result<Option<()>> {
         let url =
             self.gen_inbounds_url(vec![&inbound_id.to_string(), "delClientByEmail", email])?;
-        let res: NullObjectResponse = self
-            .send_with_retry(self.client.post(url))
-            .await?
-            .json_verbose()
-            .await?;
-        res.into_result()
+        self.fetch(
+            Call::new("del_client_by_email")
+                .inbound(inbound_id)
+                .email(email),
+            self.client.post(url),
+        )
+        .await
     }
 
     /// Get server status.
     pub async fn server_status(&self) -> Result<Option<ServerStatus>> {
         let url = self.gen_server_url(vec!["status"])?;
-        let res: ServerStatusResponse = self
-            .send_with_retry(self.client.get(url))
-            .await?
-            .json_verbose()
-            .await?;
-        res.into_result()
+        self.fetch(Call::new("server_status"), self.client.get(url))
+            .await
     }
 
     /// Download server database.
     pub async fn server_get_db(&self) -> Result<Vec<u8>> {
         let url = self.gen_server_url(vec!["getDb"])?;
-        let res = self.send_with_retry(self.client.get(url)).await?;
-        Ok(res.bytes().await?.to_vec())
+        self.send(
+            Call::new("server_get_db"),
+            self.client.get(url),
+            async |res| Ok(res.bytes().await?.to_vec()),
+        )
+        .await
     }
 
     /// Get Xray versions.
     pub async fn get_xray_version(&self) -> Result<Option<Vec<String>>> {
         let url = self.gen_server_url(vec!["getXrayVersion"])?;
-        let res: OptStringVecResponse = self
-            .send_with_retry(self.client.get(url))
-            .await?
-            .json_verbose()
-            .await?;
-        res.into_result()
+        self.fetch(Call::new("get_xray_version"), self.client.get(url))
+            .await
     }
 
     /// Get config JSON.
     pub async fn get_config_json(&self) -> Result<ConfigJson> {
         let url = self.gen_server_url(vec!["getConfigJson"])?;
-        let res: ConfigJsonResponse = self
-            .send_with_retry(self.client.get(url))
-            .await?
-            .json_verbose()
-            .await?;
-        res.into_result()
+        self.fetch(Call::new("get_config_json"), self.client.get(url))
+            .await
     }
 
     /// Get CPU history for a time bucket.
     pub async fn cpu_history(&self, minutes: u32) -> Result<Option<Vec<CpuHistoryPoint>>> {
         let url = self.gen_server_url(vec!["cpuHistory", &minutes.to_string()])?;
-        let res: CpuHistoryResponse = self
-            .send_with_retry(self.client.get(url))
-            .await?
-            .json_verbose()
-            .await?;
-        res.into_result()
+        self.fetch(Call::new("cpu_history"), self.client.get(url))
+            .await
     }
 
     /// Request a new UUID.
     pub async fn get_new_uuid(&self) -> Result<Uuid> {
         let url = self.gen_server_url(vec!["getNewUUID"])?;
-        let res: UuidResponse = self
-            .send_with_retry(self.client.get(url))
-            .await?
-            .json_verbose()
-            .await?;
-        res.into_result()
+        self.fetch(Call::new("get_new_uuid"), self.client.get(url))
+            .await
     }
 
     /// Request a new X25519 certificate.
     pub async fn get_new_x25519_cert(&self) -> Result<X25519Cert> {
         let url = self.gen_server_url(vec!["getNewX25519Cert"])?;
-        let res: X25519CertResponse = self
-            .send_with_retry(self.client.get(url))
-            .await?
-            .json_verbose()
-            .await?;
-        res.into_result()
+        self.fetch(Call::new("get_new_x25519_cert"), self.client.get(url))
+            .await
     }
 
     /// Request a new MLDsa65 bundle.
     pub async fn get_new_mldsa65(&self) -> Result<Mldsa65> {
         let url = self.gen_server_url(vec!["getNewmldsa65"])?;
-        let res: Mldsa65Response = self
-            .send_with_retry(self.client.get(url))
-            .await?
-            .json_verbose()
-            .await?;
-        res.into_result()
+        self.fetch(Call::new("get_new_mldsa65"), self.client.get(url))
+            .await
     }
 
     /// Request a new MLKEM768 bundle.
     pub async fn get_new_mlkem768(&self) -> Result<Mlkem768> {
         let url = self.gen_server_url(vec!["getNewmlkem768"])?;
-        let res: Mlkem768Response = self
-            .send_with_retry(self.client.get(url))
-            .await?
-            .json_verbose()
-            .await?;
-        res.into_result()
+        self.fetch(Call::new("get_new_mlkem768"), self.client.get(url))
+            .await
     }
 
     /// Request VLESS encryption settings.
     pub async fn get_new_vless_enc(&self) -> Result<VlessEnc> {
         let url = self.gen_server_url(vec!["getNewVlessEnc"])?;
-        let res: VlessEncResponse = self
-            .send_with_retry(self.client.get(url))
-            .await?
-            .json_verbose()
-            .await?;
-        res.into_result()
+        self.fetch(Call::new("get_new_vless_enc"), self.client.get(url))
+            .await
     }
 
     /// Request a new ECH certificate.
     pub async fn get_new_ech_cert(&self) -> Result<EchCert> {
         let url = self.gen_server_url(vec!["getNewEchCert"])?;
-        let res: EchCertResponse = self
-            .send_with_retry(self.client.post(url))
-            .await?
-            .json_verbose()
-            .await?;
-        res.into_result()
+        self.fetch(Call::new("get_new_ech_cert"), self.client.post(url))
+            .await
     }
 
     /// Stop Xray service.
     pub async fn stop_xray_service(&self) -> Result<Option<()>> {
         let url = self.gen_server_url(vec!["stopXrayService"])?;
-        let res: NullObjectResponse = self
-            .send_with_retry(self.client.post(url))
-            .await?
-            .json_verbose()
-            .await?;
-        res.into_result()
+        self.fetch(Call::new("stop_xray_service"), self.client.post(url))
+            .await
     }
 
     /// Restart Xray service.
     pub async fn restart_xray_service(&self) -> Result<Option<()>> {
         let url = self.gen_server_url(vec!["restartXrayService"])?;
-        let res: NullObjectResponse = self
-            .send_with_retry(self.client.post(url))
-            .await?
-            .json_verbose()
+        self.fetch(Call::new("restart_xray_service"), self.client.post(url))
+            .await
+    }
+
+    /// Get all panel settings.
+    pub async fn get_all_settings(&self) -> Result<AllSetting> {
+        let url = self.gen_setting_url(vec!["all"])?;
+        self.fetch(Call::new("get_all_settings"), self.client.post(url))
+            .await
+    }
+
+    /// Replace the panel settings; takes effect after [`restart_panel`](Self::restart_panel).
+    pub async fn update_settings(&self, settings: &AllSetting) -> Result<Option<()>> {
+        let url = self.gen_setting_url(vec!["update"])?;
+        self.fetch(
+            Call::new("update_settings"),
+            self.client.post(url).json(settings),
+        )
+        .await
+    }
+
+    /// Get the default Xray config the panel ships with.
+    pub async fn get_default_xray_config(&self) -> Result<ConfigJson> {
+        let url = self.gen_setting_url(vec!["getDefaultJsonConfig"])?;
+        self.fetch(Call::new("get_default_xray_config"), self.client.get(url))
+            .await
+    }
+
+    /// Restart the panel; it stops answering for a few seconds.
+    pub async fn restart_panel(&self) -> Result<Option<()>> {
+        let url = self.gen_setting_url(vec!["restartPanel"])?;
+        self.fetch(Call::new("restart_panel"), self.client.post(url))
+            .await
+    }
+
+    /// Get the Xray config template the panel generates the runtime config from.
+    pub async fn get_xray_template(&self) -> Result<ConfigJson> {
+        let url = self.gen_xray_url(vec![""])?;
+        let obj: Value = self
+            .fetch(Call::new("get_xray_template"), self.client.post(url))
             .await?;
-        res.into_result()
+        let setting: XraySetting = decode_obj(obj)?;
+        Ok(setting.xray_setting)
+    }
+
+    /// Replace the Xray config template; takes effect after Xray restarts.
+    pub async fn update_xray_template(&self, template: &ConfigJson) -> Result<Option<()>> {
+        let url = self.gen_xray_url(vec!["update"])?;
+        let json_str = serde_json::to_string(template)
+            .map_err(|e| Error::OtherError(format!("serialize xray template: {e}")))?;
+        let form = [("xraySetting", json_str)];
+        self.fetch(
+            Call::new("update_xray_template"),
+            self.client.post(url).form(&form),
+        )
+        .await
+    }
+
+    /// Error Xray reported on its last start, or `None` if it is running cleanly.
+    pub async fn get_xray_result(&self) -> Result<Option<String>> {
+        let url = self.gen_xray_url(vec!["getXrayResult"])?;
+        let result: Option<String> = self
+            .fetch(Call::new("get_xray_result"), self.client.get(url))
+            .await?;
+        Ok(result.filter(|r| !r.is_empty()))
+    }
+
+    /// Modify the Xray template with `change`, save it and restart Xray.
+    ///
+    /// Returns the error Xray reported after the restart, or `None` if it came up cleanly.
+    pub async fn apply_xray_template<F>(&self, change: F) -> Result<Option<String>>
+    where
+        F: FnOnce(&mut ConfigJson),
+    {
+        let mut template = self.get_xray_template().await?;
+        change(&mut template);
+        self.update_xray_template(&template).await?;
+        self.restart_xray_service().await?;
+        self.get_xray_result().await
+    }
+
+    /// Get traffic per outbound, keyed by outbound tag.
+    pub async fn get_outbounds_traffic(&self) -> Result<BTreeMap<String, OutboundTraffic>> {
+        let url = self.gen_xray_url(vec!["getOutboundsTraffic"])?;
+        let traffic: Option<Vec<OutboundTraffic>> = self
+            .fetch(Call::new("get_outbounds_traffic"), self.client.get(url))
+            .await?;
+        Ok(traffic
+            .unwrap_or_default()
+            .into_iter()
+            .map(|t| (t.tag.clone(), t))
+            .collect())
+    }
+
+    /// Reset the traffic counters of the outbound tagged `tag`.
+    pub async fn reset_outbound_traffic(&self, tag: &str) -> Result<()> {
+        let url = self.gen_xray_url(vec!["resetOutboundsTraffic"])?;
+        let _: Value = self
+            .fetch(
+                Call::new("reset_outbound_traffic"),
+                self.client.post(url).form(&[("tag", tag)]),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Reset the traffic counters of every outbound.
+    pub async fn reset_all_outbounds_traffic(&self) -> Result<()> {
+        self.reset_outbound_traffic(ALL_OUTBOUND_TAGS).await
+    }
+
+    /// Get the stored WARP account credentials, or `None` if no account is registered.
+    pub async fn get_warp_data(&self) -> Result<Option<WarpData>> {
+        let obj = self.warp("get_warp_data", "data", &[]).await?;
+        match obj {
+            Value::String(ref s) if s.is_empty() => Ok(None),
+            Value::Null => Ok(None),
+            obj => decode_obj(obj).map(Some),
+        }
+    }
+
+    /// Forget the stored WARP account.
+    pub async fn del_warp_data(&self) -> Result<()> {
+        self.warp("del_warp_data", "del", &[]).await?;
+        Ok(())
+    }
+
+    /// Fetch the registered WARP device, including its WireGuard config, from Cloudflare.
+    pub async fn get_warp_config(&self) -> Result<WarpDevice> {
+        decode_obj(self.warp("get_warp_config", "config", &[]).await?)
+    }
+
+    /// Register a new WARP device for a WireGuard key pair (standard base64 keys).
+    pub async fn reg_warp(&self, private_key: &str, public_key: &str) -> Result<WarpRegistration> {
+        let form = [("privateKey", private_key), ("publicKey", public_key)];
+        decode_obj(self.warp("reg_warp", "reg", &form).await?)
+    }
+
+    /// Attach a WARP+ license key to the registered account.
+    pub async fn set_warp_license(&self, license: &str) -> Result<WarpData> {
+        let form = [("license", license)];
+        decode_obj(self.warp("set_warp_license", "license", &form).await?)
+    }
+
+    async fn warp(
+        &self,
+        endpoint: &'static str,
+        action: &str,
+        form: &[(&str, &str)],
+    ) -> Result<Value> {
+        let url = self.gen_xray_url(vec!["warp", action])?;
+        self.fetch(Call::new(endpoint), self.client.post(url).form(form))
+            .await
     }
 
     /// Install Xray version.
     pub async fn install_xray_version(&self, version: &str) -> Result<Option<()>> {
         let url = self.gen_server_url(vec!["installXray", version])?;
-        let res: NullObjectResponse = self
-            .send_with_retry(self.client.post(url))
-            .await?
-            .json_verbose()
-            .await?;
-        res.into_result()
+        self.fetch(Call::new("install_xray_version"), self.client.post(url))
+            .await
     }
 
     /// Update geofile bundle.
     pub async fn update_geofile(&self) -> Result<Option<()>> {
         let url = self.gen_server_url(vec!["updateGeofile"])?;
-        let res: NullObjectResponse = self
-            .send_with_retry(self.client.post(url))
-            .await?
-            .json_verbose()
-            .await?;
-        res.into_result()
+        self.fetch(Call::new("update_geofile"), self.client.post(url))
+            .await
     }
 
     /// Update geofile by name.
     pub async fn update_geofile_by_name(&self, file_name: &str) -> Result<Option<()>> {
         let url = self.gen_server_url(vec!["updateGeofile", file_name])?;
-        let res: NullObjectResponse = self
-            .send_with_retry(self.client.post(url))
-            .await?
-            .json_verbose()
-            .await?;
-        res.into_result()
+        self.fetch(Call::new("update_geofile_by_name"), self.client.post(url))
+            .await
     }
 
     /// Fetch server logs.
     pub async fn logs(&self, count: u32) -> Result<Vec<String>> {
         let url = self.gen_server_url(vec!["logs", &count.to_string()])?;
-        let res: StringVecResponse = self
-            .send_with_retry(self.client.post(url))
-            .await?
-            .json_verbose()
-            .await?;
-        res.into_result()
+        self.fetch(Call::new("logs"), self.client.post(url)).await
     }
 
     /// Fetch Xray logs.
     pub async fn xray_logs(&self, count: u32) -> Result<Option<Vec<String>>> {
         let url = self.gen_server_url(vec!["xraylogs", &count.to_string()])?;
-        let res: OptStringVecResponse = self
-            .send_with_retry(self.client.post(url))
-            .await?
-            .json_verbose()
-            .await?;
-        res.into_result()
+        self.fetch(Call::new("xray_logs"), self.client.post(url))
+            .await
     }
 
     /// Import DB upload.
     pub async fn import_db_upload(&self, filename: &str, bytes: Vec<u8>) -> Result<String> {
         let url = self.gen_server_url(vec!["importDB"])?;
-        let form = Form::new().part("db", Part::bytes(bytes).file_name(filename.to_string()));
-        let res: StringResponse = self
-            .send_with_retry(self.client.post(url).multipart(form))
-            .await?
-            .json_verbose()
-            .await?;
-        res.into_result()
+        let build = || {
+            let part = Part::bytes(bytes.clone()).file_name(filename.to_string());
+            self.client
+                .post(url.clone())
+                .multipart(Form::new().part("db", part))
+        };
+        self.fetch_rebuilt(Call::new("import_db_upload"), &build)
+            .await
     }
 
-    async fn send_with_retry(&self, builder: reqwest::RequestBuilder) -> Result<reqwest::Response> {
-        if self.options.retry_count == 0 || builder.try_clone().is_none() {
-            return Ok(builder.send().await?);
+    /// Send `call` and hand the response to `read`; the deadline and cancellation cover both.
+    async fn send<T>(
+        &self,
+        call: Call<'_>,
+        builder: reqwest::RequestBuilder,
+        read: impl AsyncFnOnce(reqwest::Response) -> Result<T>,
+    ) -> Result<T> {
+        self.send_with(call, builder, None, read).await
+    }
+
+    async fn send_with<T>(
+        &self,
+        call: Call<'_>,
+        builder: reqwest::RequestBuilder,
+        rebuild: Option<Rebuild<'_>>,
+        read: impl AsyncFnOnce(reqwest::Response) -> Result<T>,
+    ) -> Result<T> {
+        let endpoint = call.endpoint;
+        let overrides = CallOptions::current();
+        telemetry::traced(
+            call,
+            self.options.trace_emails,
+            overrides
+                .guard(async { read(self.send_request(endpoint, builder, rebuild).await?).await }),
+        )
+        .await
+    }
+
+    /// `rebuild` makes a fresh copy of a request whose body cannot be cloned, e.g. multipart.
+    async fn send_request(
+        &self,
+        endpoint: &'static str,
+        builder: reqwest::RequestBuilder,
+        rebuild: Option<Rebuild<'_>>,
+    ) -> Result<reqwest::Response> {
+        if Capabilities::min_version(endpoint).is_some() {
+            self.capabilities().await?.check(endpoint)?;
+        }
+        self.ensure_logged_in().await?;
+        let mut generation = self.session_generation.load(Ordering::Acquire);
+        if let Some(token) = self.token()
+            && token.expires_within(self.options.token_refresh_margin)
+        {
+            if self.credentials.is_some() {
+                debug!("Bearer token is about to expire, logging in again");
+                self.relogin(generation).await?;
+            } else if let Some(provider) = &self.options.token_provider {
+                debug!("Bearer token is about to expire, fetching a new one");
+                self.refresh_token(provider.as_ref(), generation).await?;
+            } else if token.expires_within(Duration::ZERO) {
+                return Err(Error::TokenExpired);
+            }
+            generation = self.session_generation.load(Ordering::Acquire);
+        }
+        let request = builder.build()?;
+        telemetry::record_method(request.method());
+        let requested = request.url().clone();
+        let replay = request.try_clone();
+        let response = self.dispatch_request(endpoint, request).await?;
+        // Without credentials there is no way to log in again, so the caller sees the real status.
+        if self.credentials.is_none() || !self.is_session_expired(Some(&requested), &response) {
+            return Ok(response);
         }
 
-        let mut last_err: Option<reqwest::Error> = None;
-        for attempt in 0..=self.options.retry_count {
-            let cloned = builder
-                .try_clone()
-                .ok_or_else(|| Error::OtherError("request is not clonable for retry".into()))?;
-            let request = cloned.build()?;
-            let method = request.method().clone();
-            let response = self.client.execute(request).await;
-            match response {
-                Ok(resp) => {
-                    if attempt < self.options.retry_count
-                        && self.should_retry_status(&method, &resp)
-                    {
-                        let delay = self.retry_delay(attempt, resp.headers().get(RETRY_AFTER));
-                        sleep(delay).await;
-                        continue;
-                    }
-                    return Ok(resp);
-                }
-                Err(err) => {
-                    if attempt < self.options.retry_count && self.should_retry_error(&method, &err)
-                    {
-                        last_err = Some(err);
-                        sleep(self.retry_delay(attempt, None)).await;
-                        continue;
-                    }
-                    return Err(err.into());
-                }
+        debug!("Panel session expired, logging in again");
+        self.relogin(generation).await?;
+        let replay = match (replay, rebuild) {
+            (Some(replay), _) => replay,
+            (None, Some(rebuild)) => rebuild().build()?,
+            (None, None) => {
+                return Err(Error::OtherError(format!(
+                    "{endpoint}: request is not replayable after re-login"
+                )));
             }
+        };
+        self.dispatch_request(endpoint, replay).await
+    }
+
+    /// Send `call` and unwrap the panel envelope, classifying failures by endpoint.
+    async fn fetch<T: DeserializeOwned + 'static>(
+        &self,
+        call: Call<'_>,
+        builder: reqwest::RequestBuilder,
+    ) -> Result<T> {
+        self.fetch_with(call, builder, None).await
+    }
+
+    /// [`Client::fetch`] for a body that must be rebuilt to be sent again after re-login.
+    async fn fetch_rebuilt<T: DeserializeOwned + 'static>(
+        &self,
+        call: Call<'_>,
+        build: Rebuild<'_>,
+    ) -> Result<T> {
+        self.fetch_with(call, build(), Some(build)).await
+    }
+
+    async fn fetch_with<T: DeserializeOwned + 'static>(
+        &self,
+        call: Call<'_>,
+        builder: reqwest::RequestBuilder,
+        rebuild: Option<Rebuild<'_>>,
+    ) -> Result<T> {
+        let endpoint = call.endpoint;
+        let (status, envelope) = self
+            .send_with(call, builder, rebuild, async |res| {
+                let status = res.status();
+                // Failures usually carry `obj: null`, so decode it as optional to reach `msg`.
+                let envelope: Response<Option<T>> =
+                    res.json_verbose().await.map_err(|e| match e {
+                        JsonVerboseError::Status { status, body } => Error::HttpStatus {
+                            endpoint,
+                            status,
+                            body,
+                        },
+                        e => e.into(),
+                    })?;
+                Ok((status, envelope))
+            })
+            .await?;
+        match envelope.into_result_for(endpoint, status)? {
+            Some(object) => Ok(object),
+            None => T::deserialize(Value::Null).map_err(|source| {
+                JsonVerboseError::Decode {
+                    source,
+                    path: "obj".into(),
+                    body: "null".into(),
+                }
+                .into()
+            }),
         }
+    }
+
+    async fn dispatch(
+        &self,
+        endpoint: &'static str,
+        builder: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response> {
+        self.dispatch_request(endpoint, builder.build()?).await
+    }
+
+    async fn dispatch_request(
+        &self,
+        endpoint: &'static str,
+        request: Request,
+    ) -> Result<reqwest::Response> {
+        let _permit = match &self.in_flight {
+            Some(in_flight) => Some(
+                in_flight
+                    .acquire()
+                    .await
+                    .map_err(|e| Error::OtherError(e.to_string()))?,
+            ),
+            None => None,
+        };
+        Next::new(self, endpoint, &self.options.layers)
+            .run(request)
+            .await
+    }
 
-        if let Some(err) = last_err {
-            return Err(err.into());
+    pub(crate) async fn execute(&self, request: Request) -> Result<reqwest::Response> {
+        let response = self
+            .client
+            .execute(request)
+            .await
+            .map_err(|e| self.transport_error(e))?;
+        telemetry::record_status(response.status());
+        Ok(response)
+    }
+
+    pub(crate) fn authorize(&self, mut request: Request) -> Request {
+        if let Some(token) = self.token()
+            && let Ok(mut value) = HeaderValue::from_str(&format!("Bearer {}", token.token))
+        {
+            value.set_sensitive(true);
+            request.headers_mut().insert(AUTHORIZATION, value);
         }
-        Err(Error::OtherError("request retry failed".into()))
+        request
     }
 
-    fn should_retry_status(&self, method: &Method, resp: &reqwest::Response) -> bool {
-        if !self.is_idempotent(method) {
-            return false;
+    pub(crate) fn rate_limiter(&self) -> Option<&TokenBucket> {
+        self.rate_limiter.as_ref()
+    }
+
+    pub(crate) fn breaker(&self) -> Option<&Breaker> {
+        self.breaker.as_ref()
+    }
+
+    /// Timeout for one attempt: the per-call override or `request_timeout`, capped by the deadline.
+    pub(crate) fn attempt_timeout(&self) -> Duration {
+        let overrides = CallOptions::current();
+        let timeout = overrides.timeout.unwrap_or(self.options.request_timeout);
+        overrides
+            .remaining()
+            .map_or(timeout, |remaining| timeout.min(remaining))
+    }
+
+    /// Report a transport timeout as [`Error::DeadlineExceeded`] once the call's deadline has passed.
+    pub(crate) fn deadline_error(&self, err: Error) -> Error {
+        match err {
+            Error::Connection(e)
+                if e.is_timeout() && CallOptions::current().remaining() == Some(Duration::ZERO) =>
+            {
+                Error::DeadlineExceeded
+            }
+            err => err,
         }
-        let status = resp.status();
-        status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
     }
 
-    fn should_retry_error(&self, method: &Method, err: &reqwest::Error) -> bool {
-        if !self.is_idempotent(method) {
-            return false;
+    fn is_session_expired(&self, requested: Option<&Url>, resp: &reqwest::Response) -> bool {
+        if matches!(
+            resp.status(),
+            StatusCode::UNAUTHORIZED | StatusCode::NOT_FOUND
+        ) {
+            return true;
         }
-        err.is_timeout() || err.is_connect()
+        if let Some(requested) = requested
+            && resp.url().path() != requested.path()
+        {
+            return true;
+        }
+        resp.headers()
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|ct| ct.starts_with("text/html"))
+    }
+
+    async fn relogin(&self, seen_generation: u64) -> Result<()> {
+        let _guard = self.relogin_lock.lock().await;
+        if self.session_generation.load(Ordering::Acquire) != seen_generation {
+            return Ok(());
+        }
+        self.do_login()
+            .await
+            .map_err(|e| Error::SessionExpired(Box::new(e)))?;
+        self.session_generation.fetch_add(1, Ordering::AcqRel);
+        Ok(())
     }
 
-    fn is_idempotent(&self, method: &Method) -> bool {
-        self.options.retry_methods.iter().any(|m| m == method)
+    async fn refresh_token(
+        &self,
+        provider: &dyn TokenProvider,
+        seen_generation: u64,
+    ) -> Result<()> {
+        let _guard = self.relogin_lock.lock().await;
+        if self.session_generation.load(Ordering::Acquire) != seen_generation {
+            return Ok(());
+        }
+        let token = provider
+            .token()
+            .await
+            .map_err(|e| Error::SessionExpired(Box::new(e)))?;
+        self.set_token(Some(token));
+        self.session_generation.fetch_add(1, Ordering::AcqRel);
+        Ok(())
     }
 
-    fn retry_delay(
+    pub(crate) async fn retry(
         &self,
-        attempt: u32,
-        retry_after: Option<&reqwest::header::HeaderValue>,
-    ) -> Duration {
-        if let Some(value) = retry_after
-            && let Ok(s) = value.to_str()
-            && let Ok(secs) = s.parse::<u64>()
+        request: Request,
+        next: Next<'_>,
+    ) -> Result<reqwest::Response> {
+        if request.try_clone().is_none() {
+            return next.run(request).await;
+        }
+
+        let overrides = CallOptions::current();
+        let method = request.method().clone();
+        let endpoint = Endpoint {
+            name: next.endpoint(),
+            method: &method,
+            retry_count: overrides.retry_count,
+        };
+        let mut attempt = 1;
+        loop {
+            let cloned = request
+                .try_clone()
+                .ok_or_else(|| Error::OtherError("request is not clonable for retry".into()))?;
+            telemetry::record_attempt(attempt);
+            let result = next.run(cloned).await;
+            let outcome = match &result {
+                Ok(resp) => Outcome::Response(resp),
+                Err(err) => Outcome::Error(err),
+            };
+            let delay = self
+                .retry_policy
+                .retry(&endpoint, attempt, outcome)
+                .filter(|_| overrides.retry_count.is_none_or(|max| attempt <= max))
+                .filter(|delay| overrides.remaining().is_none_or(|left| *delay < left));
+            match delay {
+                Some(delay) => sleep(delay).await,
+                None => return result,
+            }
+            attempt += 1;
+        }
+    }
+
+    fn transport_error(&self, err: reqwest::Error) -> Error {
+        if err.is_connect()
+            && let Some(proxy) = &self.options.proxy
+            && !proxy.bypasses(self.url.host_str().unwrap_or_default())
         {
-            return Duration::from_secs(secs);
+            return Error::Proxy(err);
+        }
+        err.into()
+    }
+}
+
+/// Check that a `ClientStats` counter fits the panel's signed 64-bit column.
+fn panel_counter(field: &'static str, value: u128) -> Result<i64> {
+    i64::try_from(value).map_err(|_| Error::CounterOverflow { field, value })
+}
+
+/// Decode an `obj` the panel sends either inline or as a JSON-encoded string.
+fn decode_obj<T: DeserializeOwned>(obj: Value) -> Result<T> {
+    let body = match obj {
+        Value::String(body) => body,
+        obj => obj.to_string(),
+    };
+    let mut de = serde_json::Deserializer::from_str(&body);
+    serde_path_to_error::deserialize(&mut de).map_err(|e| {
+        let path = e.path().to_string();
+        JsonVerboseError::Decode {
+            source: e.into_inner(),
+            path,
+            body,
         }
-        let backoff = 1u64.checked_shl(attempt).unwrap_or(u64::MAX);
-        let ms = self
-            .options
-            .retry_base_delay
-            .as_millis()
-            .saturating_mul(backoff as u128)
-            .min(self.options.retry_max_delay.as_millis());
-        Duration::from_millis(ms as u64)
+        .into()
+    })
+}
+
+fn cookie_expires_at(response: &reqwest::Response) -> Option<i64> {
+    response
+        .headers()
+        .get_all(SET_COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .filter_map(|attr| attr.trim().split_once('='))
+        .filter(|(name, _)| name.eq_ignore_ascii_case("max-age"))
+        .filter_map(|(_, value)| value.trim().parse::<i64>().ok())
+        .min()
+        .map(|max_age| unix_now().saturating_add(max_age))
+}
+
+fn build_http_client(options: &ClientOptions, cookies: Arc<Jar>) -> Result<RClient> {
+    // `Layer::Timeout` sets each attempt's timeout; this covers stacks built without it.
+    let builder = RClient::builder()
+        .cookie_provider(cookies)
+        .connect_timeout(options.connect_timeout)
+        .timeout(options.request_timeout);
+    let builder = proxy::apply(builder, options.proxy.as_ref())?;
+    Ok(tls::apply(builder, &options.tls)?.build()?)
+}
+
+fn normalize_base_url(mut url: Url, web_base_path: Option<&str>) -> Result<Url> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(Error::InvalidUrl(format!(
+            "unsupported scheme `{}`, expected http or https",
+            url.scheme()
+        )));
+    }
+    if url.cannot_be_a_base() || url.host_str().is_none() {
+        return Err(Error::InvalidUrl(format!("`{url}` cannot be a base URL")));
+    }
+    if url.query().is_some() || url.fragment().is_some() {
+        return Err(Error::InvalidUrl(format!(
+            "base URL `{url}` must not contain a query or fragment"
+        )));
+    }
+
+    let url_segs: Vec<String> = url
+        .path_segments()
+        .into_iter()
+        .flatten()
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect();
+    let base_segs: Vec<&str> = web_base_path
+        .unwrap_or_default()
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .collect();
+    if let Some(bad) = base_segs
+        .iter()
+        .find(|s| matches!(**s, "." | "..") || s.contains(['?', '#', '\\']))
+    {
+        return Err(Error::InvalidUrl(format!(
+            "invalid segment `{bad}` in web_base_path"
+        )));
+    }
+    if !url_segs.is_empty() && !base_segs.is_empty() {
+        return Err(Error::InvalidUrl(format!(
+            "base URL already has path `{}`; set either the URL path or web_base_path",
+            url.path()
+        )));
+    }
+
+    {
+        let mut path_segments = url
+            .path_segments_mut()
+            .map_err(|_| Error::InvalidUrl("Cannot be a base URL".into()))?;
+        path_segments.clear();
+        path_segments.extend(url_segs.iter().map(String::as_str));
+        path_segments.extend(base_segs);
     }
+    Ok(url)
 }
@@ -0,0 +1,215 @@
+use crate::Result;
+use crate::client::AuthToken;
+use crate::error::Error;
+use futures::future::BoxFuture;
+use std::fmt;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Arc;
+use zeroize::Zeroize;
+
+/// String that never shows up in `Debug`/`Display` output and is zeroized on drop.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct SecretString(String);
+
+impl SecretString {
+    pub fn new(secret: impl Into<String>) -> Self {
+        Self(secret.into())
+    }
+
+    /// Access the secret value.
+    pub fn expose_secret(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Drop for SecretString {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("<redacted>")
+    }
+}
+
+impl fmt::Display for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("<redacted>")
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(secret: String) -> Self {
+        Self(secret)
+    }
+}
+
+impl From<&str> for SecretString {
+    fn from(secret: &str) -> Self {
+        Self(secret.to_string())
+    }
+}
+
+/// Panel username and password.
+#[derive(Debug, Clone)]
+pub struct Credentials {
+    pub username: String,
+    pub password: SecretString,
+}
+
+impl Credentials {
+    pub fn new(username: impl Into<String>, password: impl Into<SecretString>) -> Self {
+        Self {
+            username: username.into(),
+            password: password.into(),
+        }
+    }
+}
+
+/// Source of the credentials used for every login.
+pub trait CredentialProvider: fmt::Debug + Send + Sync {
+    fn credentials(&self) -> BoxFuture<'_, Result<Credentials>>;
+}
+
+impl CredentialProvider for Credentials {
+    fn credentials(&self) -> BoxFuture<'_, Result<Credentials>> {
+        Box::pin(async move { Ok(self.clone()) })
+    }
+}
+
+/// Reads username and password from environment variables on every login.
+#[derive(Debug, Clone)]
+pub struct EnvCredentials {
+    pub username_var: String,
+    pub password_var: String,
+}
+
+impl EnvCredentials {
+    pub fn new(username_var: impl Into<String>, password_var: impl Into<String>) -> Self {
+        Self {
+            username_var: username_var.into(),
+            password_var: password_var.into(),
+        }
+    }
+}
+
+impl Default for EnvCredentials {
+    /// `PANEL_USERNAME` / `PANEL_PASSWORD`.
+    fn default() -> Self {
+        Self::new("PANEL_USERNAME", "PANEL_PASSWORD")
+    }
+}
+
+impl CredentialProvider for EnvCredentials {
+    fn credentials(&self) -> BoxFuture<'_, Result<Credentials>> {
+        Box::pin(async move {
+            let read = |var: &str| {
+                std::env::var(var)
+                    .map_err(|e| Error::OtherError(format!("credential variable {var}: {e}")))
+            };
+            Ok(Credentials::new(
+                read(&self.username_var)?,
+                read(&self.password_var)?,
+            ))
+        })
+    }
+}
+
+/// Reads the password from a file (e.g. a Docker or Kubernetes secret) on every login.
+/// A single trailing newline is stripped.
+#[derive(Debug, Clone)]
+pub struct FileCredentials {
+    pub username: String,
+    pub password_file: PathBuf,
+}
+
+impl FileCredentials {
+    pub fn new(username: impl Into<String>, password_file: impl Into<PathBuf>) -> Self {
+        Self {
+            username: username.into(),
+            password_file: password_file.into(),
+        }
+    }
+}
+
+impl CredentialProvider for FileCredentials {
+    fn credentials(&self) -> BoxFuture<'_, Result<Credentials>> {
+        Box::pin(async move {
+            let mut password = tokio::fs::read_to_string(&self.password_file)
+                .await
+                .map_err(|e| {
+                    Error::OtherError(format!(
+                        "password file {}: {e}",
+                        self.password_file.display()
+                    ))
+                })?;
+            let len = password.trim_end_matches('\n').trim_end_matches('\r').len();
+            password.truncate(len);
+            Ok(Credentials::new(self.username.clone(), password))
+        })
+    }
+}
+
+type CredentialFn = dyn Fn() -> BoxFuture<'static, Result<Credentials>> + Send + Sync;
+
+/// Credentials produced by an async closure, e.g. a vault lookup.
+#[derive(Clone)]
+pub struct FnCredentials(Arc<CredentialFn>);
+
+impl FnCredentials {
+    pub fn new<F, Fut>(f: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Credentials>> + Send + 'static,
+    {
+        Self(Arc::new(move || Box::pin(f())))
+    }
+}
+
+impl fmt::Debug for FnCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("FnCredentials(..)")
+    }
+}
+
+impl CredentialProvider for FnCredentials {
+    fn credentials(&self) -> BoxFuture<'_, Result<Credentials>> {
+        (self.0)()
+    }
+}
+
+/// Source of a fresh bearer token for a client built without credentials.
+pub trait TokenProvider: fmt::Debug + Send + Sync {
+    fn token(&self) -> BoxFuture<'_, Result<AuthToken>>;
+}
+
+type TokenFn = dyn Fn() -> BoxFuture<'static, Result<AuthToken>> + Send + Sync;
+
+/// Token produced by an async closure, e.g. a call to an identity service.
+#[derive(Clone)]
+pub struct FnToken(Arc<TokenFn>);
+
+impl FnToken {
+    pub fn new<F, Fut>(f: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<AuthToken>> + Send + 'static,
+    {
+        Self(Arc::new(move || Box::pin(f())))
+    }
+}
+
+impl fmt::Debug for FnToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("FnToken(..)")
+    }
+}
+
+impl TokenProvider for FnToken {
+    fn token(&self) -> BoxFuture<'_, Result<AuthToken>> {
+        (self.0)()
+    }
+}
@@ -1,19 +1,80 @@
 use crate::response_ext::JsonVerboseError;
+use crate::version::PanelVersion;
 use reqwest::StatusCode;
 use thiserror::Error;
+use tokio::time::Duration;
 
 #[derive(Error, Debug)]
 pub enum Error {
     #[error("Invalid URL: {0}")]
     InvalidUrl(String),
+    /// Rejected before any request is sent, so [`Error::endpoint`] is `None` for it.
+    #[error("{field} = {value} exceeds the panel's 64-bit traffic counter")]
+    CounterOverflow { field: &'static str, value: u128 },
     #[error("Not found (404): {0}")]
     NotFound(#[source] reqwest::Error),
     #[error("Connection error: {0}")]
     Connection(#[source] reqwest::Error),
+    #[error("Proxy error: {0}")]
+    Proxy(#[source] reqwest::Error),
+    #[error("TLS configuration error: {0}")]
+    Tls(String),
     #[error("Invalid credentials!")]
     InvalidCred,
-    #[error("API error: {message}")]
-    ApiError { message: String },
+    #[error("Two-factor code required!")]
+    TwoFactorRequired,
+    #[error("Bearer token expired and no token provider is configured")]
+    TokenExpired,
+    #[error("Session expired: {0}")]
+    SessionExpired(#[source] Box<Error>),
+    #[error("Circuit breaker is open, retry in {retry_after:?}")]
+    CircuitOpen { retry_after: Duration },
+    #[error("Call cancelled")]
+    Cancelled,
+    #[error("Call deadline exceeded")]
+    DeadlineExceeded,
+    #[error("{endpoint} requires panel version {min_version} or newer")]
+    Unsupported {
+        endpoint: &'static str,
+        min_version: PanelVersion,
+    },
+    #[error("{endpoint}: client not found: {message}")]
+    ClientNotFound {
+        endpoint: &'static str,
+        message: String,
+    },
+    #[error("{endpoint}: duplicate email: {message}")]
+    DuplicateEmail {
+        endpoint: &'static str,
+        message: String,
+    },
+    #[error("{endpoint}: port already in use: {message}")]
+    PortInUse {
+        endpoint: &'static str,
+        message: String,
+    },
+    #[error("{endpoint}: inbound not found: {message}")]
+    InboundNotFound {
+        endpoint: &'static str,
+        message: String,
+    },
+    #[error("{endpoint}: panel session expired: {message}")]
+    AuthExpired {
+        endpoint: &'static str,
+        message: String,
+    },
+    #[error("{endpoint} returned HTTP {status}: {body}")]
+    HttpStatus {
+        endpoint: &'static str,
+        status: StatusCode,
+        body: String,
+    },
+    #[error("API error from {endpoint}: {message}")]
+    ApiError {
+        endpoint: &'static str,
+        status: StatusCode,
+        message: String,
+    },
     #[error("Error: {0}!")]
     OtherError(String),
     #[error(transparent)]
@@ -30,3 +91,110 @@ impl From<reqwest::Error> for Error {
         Error::Connection(err)
     }
 }
+
+#[derive(Clone, Copy)]
+enum Record {
+    Inbound,
+    Client,
+}
+
+/// Row each endpoint looks up first, so a bare "record not found" refers to it.
+const MISSING_RECORDS: &[(&str, Record)] = &[
+    ("get_inbound_by_id", Record::Inbound),
+    ("update_inbound", Record::Inbound),
+    ("delete_inbound", Record::Inbound),
+    ("add_client_to_inbound", Record::Inbound),
+    ("update_client", Record::Inbound),
+    ("delete_client", Record::Inbound),
+    ("del_client_by_email", Record::Inbound),
+    ("reset_client_traffic", Record::Inbound),
+    ("reset_all_client_traffics", Record::Inbound),
+    ("delete_depleted_clients", Record::Inbound),
+    ("get_client_traffic_by_email", Record::Client),
+    ("get_client_traffic_by_id", Record::Client),
+    ("clear_client_ips", Record::Client),
+    ("update_client_traffic", Record::Client),
+];
+
+fn missing_record(endpoint: &str) -> Option<Record> {
+    MISSING_RECORDS
+        .iter()
+        .find(|(name, _)| *name == endpoint)
+        .map(|(_, record)| *record)
+}
+
+impl Error {
+    /// Error for a `success: false` panel response, classified by its message.
+    pub fn from_panel_message(endpoint: &'static str, status: StatusCode, message: String) -> Self {
+        let msg = message.to_ascii_lowercase();
+        let not_found = msg.contains("not found") || msg.contains("not exist");
+        if msg.contains("duplicate email") {
+            Error::DuplicateEmail { endpoint, message }
+        } else if msg.contains("port") && (msg.contains("already") || msg.contains("in use")) {
+            Error::PortInUse { endpoint, message }
+        } else if not_found && (msg.contains("client") || msg.contains("email")) {
+            Error::ClientNotFound { endpoint, message }
+        } else if not_found && msg.contains("inbound") {
+            Error::InboundNotFound { endpoint, message }
+        } else if not_found
+            && msg.contains("record")
+            && let Some(record) = missing_record(endpoint)
+        {
+            // Gorm's "record not found" names no entity; the endpoint's lookup tells which one.
+            match record {
+                Record::Inbound => Error::InboundNotFound { endpoint, message },
+                Record::Client => Error::ClientNotFound { endpoint, message },
+            }
+        } else if status == StatusCode::UNAUTHORIZED
+            || msg.contains("session expired")
+            || msg.contains("please login")
+        {
+            Error::AuthExpired { endpoint, message }
+        } else {
+            Error::ApiError {
+                endpoint,
+                status,
+                message,
+            }
+        }
+    }
+
+    /// Client method the error came from, when known.
+    pub fn endpoint(&self) -> Option<&'static str> {
+        match self {
+            Error::ClientNotFound { endpoint, .. }
+            | Error::DuplicateEmail { endpoint, .. }
+            | Error::PortInUse { endpoint, .. }
+            | Error::InboundNotFound { endpoint, .. }
+            | Error::AuthExpired { endpoint, .. }
+            | Error::HttpStatus { endpoint, .. }
+            | Error::ApiError { endpoint, .. }
+            | Error::Unsupported { endpoint, .. } => Some(endpoint),
+            Error::SessionExpired(inner) => inner.endpoint(),
+            _ => None,
+        }
+    }
+
+    /// HTTP status of the response that caused the error, if there was one.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            Error::NotFound(e) | Error::Connection(e) | Error::Proxy(e) => e.status(),
+            Error::HttpStatus { status, .. } | Error::ApiError { status, .. } => Some(*status),
+            Error::JsonVerbose(JsonVerboseError::Status { status, .. }) => Some(*status),
+            Error::JsonVerbose(JsonVerboseError::Http(e)) => e.status(),
+            Error::SessionExpired(inner) => inner.status(),
+            _ => None,
+        }
+    }
+
+    /// Whether repeating the same call later may succeed.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::Connection(e) | Error::Proxy(e) => e.is_timeout() || e.is_connect(),
+            Error::CircuitOpen { .. } => true,
+            _ => self
+                .status()
+                .is_some_and(|s| s == StatusCode::TOO_MANY_REQUESTS || s.is_server_error()),
+        }
+    }
+}
@@ -0,0 +1,375 @@
+//! In-memory [`PanelApi`] for tests, behind the `test-util` feature.
+
+use crate::api::PanelApi;
+use crate::client::LoginResult;
+use crate::error::Error;
+use crate::models::{
+    AllSetting, ClientRequest, ClientStats, ConfigJson, CpuHistoryPoint, CreateInboundRequest,
+    EchCert, Inbounds, Mldsa65, Mlkem768, OutboundTraffic, ServerStatus, Uuid, VlessEnc, WarpData,
+    WarpDevice, WarpRegistration, X25519Cert,
+};
+use crate::version::PanelVersion;
+use crate::{ClientIpsResponse, Result};
+use futures::future::BoxFuture;
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+type Response<T> = Arc<dyn Fn() -> Result<T> + Send + Sync>;
+
+/// [`PanelApi`] fake that answers each method from a closure set with [`FakePanel::on`].
+///
+/// Arguments are ignored; methods without a response fail with [`Error::OtherError`].
+#[derive(Default)]
+pub struct FakePanel {
+    responses: BTreeMap<&'static str, Box<dyn Any + Send + Sync>>,
+    calls: Mutex<Vec<&'static str>>,
+}
+
+impl FakePanel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answer `method` (e.g. `"get_inbounds_list"`) with `response`, which must return the
+    /// method's result type.
+    pub fn on<T, F>(mut self, method: &'static str, response: F) -> Self
+    where
+        T: Send + 'static,
+        F: Fn() -> Result<T> + Send + Sync + 'static,
+    {
+        let response: Response<T> = Arc::new(response);
+        self.responses.insert(method, Box::new(response));
+        self
+    }
+
+    /// Methods called so far, in order.
+    pub fn calls(&self) -> Vec<&'static str> {
+        self.calls.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn respond<T: Send + 'static>(&self, method: &'static str) -> BoxFuture<'_, Result<T>> {
+        self.calls
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(method);
+        let result = match self.responses.get(method) {
+            Some(response) => match response.downcast_ref::<Response<T>>() {
+                Some(response) => response(),
+                None => Err(Error::OtherError(format!(
+                    "FakePanel: response for {method} has the wrong type"
+                ))),
+            },
+            None => Err(Error::OtherError(format!(
+                "FakePanel: no response for {method}"
+            ))),
+        };
+        Box::pin(async move { result })
+    }
+}
+
+impl fmt::Debug for FakePanel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FakePanel")
+            .field("responses", &self.responses.keys().collect::<Vec<_>>())
+            .field("calls", &self.calls())
+            .finish()
+    }
+}
+
+impl PanelApi for FakePanel {
+    fn login(&self) -> BoxFuture<'_, Result<LoginResult>> {
+        self.respond("login")
+    }
+
+    fn panel_version(&self) -> BoxFuture<'_, Result<Option<PanelVersion>>> {
+        self.respond("panel_version")
+    }
+
+    fn get_inbounds_list(&self) -> BoxFuture<'_, Result<Vec<Inbounds>>> {
+        self.respond("get_inbounds_list")
+    }
+
+    fn get_inbound_by_id(&self, _inbound_id: u64) -> BoxFuture<'_, Result<Inbounds>> {
+        self.respond("get_inbound_by_id")
+    }
+
+    fn get_client_traffic_by_email<'a>(
+        &'a self,
+        _email: &'a str,
+    ) -> BoxFuture<'a, Result<ClientStats>> {
+        self.respond("get_client_traffic_by_email")
+    }
+
+    fn get_client_traffic_by_id<'a>(
+        &'a self,
+        _id: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<ClientStats>>> {
+        self.respond("get_client_traffic_by_id")
+    }
+
+    fn send_backup_by_bot(&self) -> BoxFuture<'_, Result<()>> {
+        self.respond("send_backup_by_bot")
+    }
+
+    fn get_client_ips<'a>(
+        &'a self,
+        _client_email: &'a str,
+    ) -> BoxFuture<'a, Result<ClientIpsResponse>> {
+        self.respond("get_client_ips")
+    }
+
+    fn add_inbound<'a>(
+        &'a self,
+        _req: &'a CreateInboundRequest,
+    ) -> BoxFuture<'a, Result<Inbounds>> {
+        self.respond("add_inbound")
+    }
+
+    fn add_client_to_inbound<'a>(
+        &'a self,
+        _req: &'a ClientRequest,
+    ) -> BoxFuture<'a, Result<Option<()>>> {
+        self.respond("add_client_to_inbound")
+    }
+
+    fn update_inbound<'a>(
+        &'a self,
+        _inbound_id: u64,
+        _req: &'a CreateInboundRequest,
+    ) -> BoxFuture<'a, Result<Inbounds>> {
+        self.respond("update_inbound")
+    }
+
+    fn update_client<'a>(
+        &'a self,
+        _uuid: &'a str,
+        _req: &'a ClientRequest,
+    ) -> BoxFuture<'a, Result<Option<()>>> {
+        self.respond("update_client")
+    }
+
+    fn clear_client_ips<'a>(&'a self, _email: &'a str) -> BoxFuture<'a, Result<Option<()>>> {
+        self.respond("clear_client_ips")
+    }
+
+    fn reset_all_inbound_traffics(&self) -> BoxFuture<'_, Result<Option<()>>> {
+        self.respond("reset_all_inbound_traffics")
+    }
+
+    fn reset_all_client_traffics(&self, _inbound_id: u64) -> BoxFuture<'_, Result<Option<()>>> {
+        self.respond("reset_all_client_traffics")
+    }
+
+    fn reset_client_traffic<'a>(
+        &'a self,
+        _inbound_id: u64,
+        _email: &'a str,
+    ) -> BoxFuture<'a, Result<Option<()>>> {
+        self.respond("reset_client_traffic")
+    }
+
+    fn update_client_traffic<'a>(
+        &'a self,
+        _email: &'a str,
+        _up: u128,
+        _down: u128,
+    ) -> BoxFuture<'a, Result<()>> {
+        self.respond("update_client_traffic")
+    }
+
+    fn delete_client<'a>(
+        &'a self,
+        _inbound_id: u64,
+        _uuid: &'a str,
+    ) -> BoxFuture<'a, Result<Option<()>>> {
+        self.respond("delete_client")
+    }
+
+    fn delete_inbound(&self, _inbound_id: u64) -> BoxFuture<'_, Result<u64>> {
+        self.respond("delete_inbound")
+    }
+
+    fn delete_depleted_clients(&self, _inbound_id: u64) -> BoxFuture<'_, Result<Option<()>>> {
+        self.respond("delete_depleted_clients")
+    }
+
+    fn online_clients(&self) -> BoxFuture<'_, Result<Option<Vec<String>>>> {
+        self.respond("online_clients")
+    }
+
+    fn import_inbound<'a>(&'a self, _inbound: &'a Inbounds) -> BoxFuture<'a, Result<Inbounds>> {
+        self.respond("import_inbound")
+    }
+
+    fn get_last_online(&self) -> BoxFuture<'_, Result<BTreeMap<String, SystemTime>>> {
+        self.respond("get_last_online")
+    }
+
+    fn get_last_online_merged(&self) -> BoxFuture<'_, Result<BTreeMap<String, SystemTime>>> {
+        self.respond("get_last_online_merged")
+    }
+
+    fn del_client_by_email<'a>(
+        &'a self,
+        _inbound_id: u64,
+        _email: &'a str,
+    ) -> BoxFuture<'a, Result<Option<()>>> {
+        self.respond("del_client_by_email")
+    }
+
+    fn server_status(&self) -> BoxFuture<'_, Result<Option<ServerStatus>>> {
+        self.respond("server_status")
+    }
+
+    fn server_get_db(&self) -> BoxFuture<'_, Result<Vec<u8>>> {
+        self.respond("server_get_db")
+    }
+
+    fn get_xray_version(&self) -> BoxFuture<'_, Result<Option<Vec<String>>>> {
+        self.respond("get_xray_version")
+    }
+
+    fn get_config_json(&self) -> BoxFuture<'_, Result<ConfigJson>> {
+        self.respond("get_config_json")
+    }
+
+    fn cpu_history(&self, _minutes: u32) -> BoxFuture<'_, Result<Option<Vec<CpuHistoryPoint>>>> {
+        self.respond("cpu_history")
+    }
+
+    fn get_new_uuid(&self) -> BoxFuture<'_, Result<Uuid>> {
+        self.respond("get_new_uuid")
+    }
+
+    fn get_new_x25519_cert(&self) -> BoxFuture<'_, Result<X25519Cert>> {
+        self.respond("get_new_x25519_cert")
+    }
+
+    fn get_new_mldsa65(&self) -> BoxFuture<'_, Result<Mldsa65>> {
+        self.respond("get_new_mldsa65")
+    }
+
+    fn get_new_mlkem768(&self) -> BoxFuture<'_, Result<Mlkem768>> {
+        self.respond("get_new_mlkem768")
+    }
+
+    fn get_new_vless_enc(&self) -> BoxFuture<'_, Result<VlessEnc>> {
+        self.respond("get_new_vless_enc")
+    }
+
+    fn get_new_ech_cert(&self) -> BoxFuture<'_, Result<EchCert>> {
+        self.respond("get_new_ech_cert")
+    }
+
+    fn stop_xray_service(&self) -> BoxFuture<'_, Result<Option<()>>> {
+        self.respond("stop_xray_service")
+    }
+
+    fn restart_xray_service(&self) -> BoxFuture<'_, Result<Option<()>>> {
+        self.respond("restart_xray_service")
+    }
+
+    fn get_all_settings(&self) -> BoxFuture<'_, Result<AllSetting>> {
+        self.respond("get_all_settings")
+    }
+
+    fn update_settings<'a>(
+        &'a self,
+        _settings: &'a AllSetting,
+    ) -> BoxFuture<'a, Result<Option<()>>> {
+        self.respond("update_settings")
+    }
+
+    fn get_default_xray_config(&self) -> BoxFuture<'_, Result<ConfigJson>> {
+        self.respond("get_default_xray_config")
+    }
+
+    fn restart_panel(&self) -> BoxFuture<'_, Result<Option<()>>> {
+        self.respond("restart_panel")
+    }
+
+    fn get_xray_template(&self) -> BoxFuture<'_, Result<ConfigJson>> {
+        self.respond("get_xray_template")
+    }
+
+    fn update_xray_template<'a>(
+        &'a self,
+        _template: &'a ConfigJson,
+    ) -> BoxFuture<'a, Result<Option<()>>> {
+        self.respond("update_xray_template")
+    }
+
+    fn get_xray_result(&self) -> BoxFuture<'_, Result<Option<String>>> {
+        self.respond("get_xray_result")
+    }
+
+    fn get_outbounds_traffic(&self) -> BoxFuture<'_, Result<BTreeMap<String, OutboundTraffic>>> {
+        self.respond("get_outbounds_traffic")
+    }
+
+    fn reset_outbound_traffic<'a>(&'a self, _tag: &'a str) -> BoxFuture<'a, Result<()>> {
+        self.respond("reset_outbound_traffic")
+    }
+
+    fn reset_all_outbounds_traffic(&self) -> BoxFuture<'_, Result<()>> {
+        self.respond("reset_all_outbounds_traffic")
+    }
+
+    fn get_warp_data(&self) -> BoxFuture<'_, Result<Option<WarpData>>> {
+        self.respond("get_warp_data")
+    }
+
+    fn del_warp_data(&self) -> BoxFuture<'_, Result<()>> {
+        self.respond("del_warp_data")
+    }
+
+    fn get_warp_config(&self) -> BoxFuture<'_, Result<WarpDevice>> {
+        self.respond("get_warp_config")
+    }
+
+    fn reg_warp<'a>(
+        &'a self,
+        _private_key: &'a str,
+        _public_key: &'a str,
+    ) -> BoxFuture<'a, Result<WarpRegistration>> {
+        self.respond("reg_warp")
+    }
+
+    fn set_warp_license<'a>(&'a self, _license: &'a str) -> BoxFuture<'a, Result<WarpData>> {
+        self.respond("set_warp_license")
+    }
+
+    fn install_xray_version<'a>(&'a self, _version: &'a str) -> BoxFuture<'a, Result<Option<()>>> {
+        self.respond("install_xray_version")
+    }
+
+    fn update_geofile(&self) -> BoxFuture<'_, Result<Option<()>>> {
+        self.respond("update_geofile")
+    }
+
+    fn update_geofile_by_name<'a>(
+        &'a self,
+        _file_name: &'a str,
+    ) -> BoxFuture<'a, Result<Option<()>>> {
+        self.respond("update_geofile_by_name")
+    }
+
+    fn logs(&self, _count: u32) -> BoxFuture<'_, Result<Vec<String>>> {
+        self.respond("logs")
+    }
+
+    fn xray_logs(&self, _count: u32) -> BoxFuture<'_, Result<Option<Vec<String>>>> {
+        self.respond("xray_logs")
+    }
+
+    fn import_db_upload<'a>(
+        &'a self,
+        _filename: &'a str,
+        _bytes: Vec<u8>,
+    ) -> BoxFuture<'a, Result<String>> {
+        self.respond("import_db_upload")
+    }
+}
@@ -1,26 +1,65 @@
 #![doc = include_str!("../README.md")]
 extern crate core;
 
-use crate::error::Error;
 use crate::models::{
-    ClientIps, ConfigJson, CpuHistoryPoint, EchCert, LoginInfo, Mldsa65, Mlkem768, Response,
-    ServerStatus, Uuid, VlessEnc, X25519Cert,
+    AllSetting, ClientIps, ConfigJson, CpuHistoryPoint, EchCert, LoginInfo, Mldsa65, Mlkem768,
+    Response, ServerStatus, Uuid, VlessEnc, X25519Cert,
 };
+pub use api::PanelApi;
+pub use batch::BatchMode;
+pub use builder::ClientBuilder;
+pub use call_options::{CallOptions, CancellationToken};
+pub use client::AuthToken;
 pub use client::Client;
 pub use client::ClientOptions;
 pub use client::LoginResult;
+pub use credentials::{
+    CredentialProvider, Credentials, EnvCredentials, FileCredentials, FnCredentials, FnToken,
+    SecretString, TokenProvider,
+};
+pub use error::Error;
+#[cfg(feature = "test-util")]
+pub use fake::FakePanel;
+pub use limits::{CircuitBreaker, RateLimit};
+pub use middleware::{Layer, Middleware, Next};
 use models::{ClientStats, Inbounds};
+pub use proxy::ProxyOptions;
+pub use retry::{Endpoint, ExponentialBackoff, Jitter, Outcome, RetryPolicy};
 use serde_json::Value;
+pub use session::{FileSessionStore, SessionStore, StoredSession};
+pub use telemetry::EmailField;
+pub use tls::TlsOptions;
+pub use two_factor::TwoFactor;
+pub use version::{Capabilities, PanelVersion};
 
+pub mod api;
+pub mod batch;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod builder;
+pub mod call_options;
 pub mod client;
+pub mod credentials;
 pub mod error;
+#[cfg(feature = "test-util")]
+pub mod fake;
 pub mod inbounds;
+pub mod limits;
+pub mod middleware;
 pub mod models;
+pub mod proxy;
 pub mod response_ext;
+pub mod retry;
+pub mod session;
+pub mod telemetry;
+pub mod tls;
+pub mod two_factor;
+pub mod version;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
 pub type NullObjectResponse = Response<Option<()>>;
+pub type BoolResponse = Response<bool>;
 pub type InboundsResponse = Response<Vec<Inbounds>>;
 pub type InboundResponse = Response<Inbounds>;
 pub type ClientsStatsVecResponse = Response<Vec<ClientStats>>;
@@ -42,3 +81,4 @@ pub type Mlkem768Response = Response<Mlkem768>;
 pub type VlessEncResponse = Response<VlessEnc>;
 pub type EchCertResponse = Response<EchCert>;
 pub type LoginResponse = Response<Option<LoginInfo>>;
+pub type AllSettingResponse = Response<AllSetting>;
@@ -0,0 +1,213 @@
+use crate::Result;
+use crate::error::Error;
+use crate::middleware::Next;
+use reqwest::{Request, Response, StatusCode};
+use std::sync::Mutex;
+use tokio::time::{Duration, Instant, sleep_until};
+
+/// Token-bucket limit on outgoing requests, applied to every attempt.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    pub requests_per_second: f64,
+    /// Requests that may be sent back to back before throttling starts.
+    pub burst: u32,
+}
+
+impl RateLimit {
+    pub fn new(requests_per_second: f64, burst: u32) -> Self {
+        Self {
+            requests_per_second,
+            burst,
+        }
+    }
+}
+
+/// Circuit breaker settings.
+///
+/// After `failure_threshold` consecutive failures (transport errors, `429` or `5xx`) the breaker
+/// opens and calls fail with [`Error::CircuitOpen`] for `cool_down`. Then up to
+/// `half_open_probes` requests are let through; if they all succeed the breaker closes again,
+/// and any failure reopens it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitBreaker {
+    pub failure_threshold: u32,
+    pub cool_down: Duration,
+    pub half_open_probes: u32,
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            cool_down: Duration::from_secs(30),
+            half_open_probes: 1,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct TokenBucket {
+    limit: RateLimit,
+    state: Mutex<(f64, Instant)>,
+}
+
+impl TokenBucket {
+    pub fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            state: Mutex::new((limit.burst.max(1) as f64, Instant::now())),
+        }
+    }
+
+    /// Take a token, waiting for one to be refilled if the bucket is empty.
+    pub async fn acquire(&self) {
+        let rate = self.limit.requests_per_second;
+        if rate <= 0.0 || !rate.is_finite() {
+            return;
+        }
+        let ready_at = {
+            let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            let (tokens, last) = &mut *state;
+            let now = Instant::now();
+            let capacity = self.limit.burst.max(1) as f64;
+            *tokens = (*tokens + now.duration_since(*last).as_secs_f64() * rate).min(capacity);
+            *last = now;
+            *tokens -= 1.0;
+            if *tokens >= 0.0 {
+                return;
+            }
+            now + Duration::from_secs_f64(-*tokens / rate)
+        };
+        sleep_until(ready_at).await;
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum State {
+    Closed { failures: u32 },
+    Open { until: Instant },
+    HalfOpen { in_flight: u32, successes: u32 },
+}
+
+#[derive(Debug)]
+pub(crate) struct Breaker {
+    options: CircuitBreaker,
+    state: Mutex<State>,
+}
+
+impl Breaker {
+    pub fn new(options: CircuitBreaker) -> Self {
+        Self {
+            options,
+            state: Mutex::new(State::Closed { failures: 0 }),
+        }
+    }
+
+    /// Send the request through `next` unless the breaker is open.
+    pub async fn call(&self, request: Request, next: Next<'_>) -> Result<Response> {
+        let mut probe = Probe {
+            breaker: self,
+            probe: self.admit()?,
+            done: false,
+        };
+        let result = next.run(request).await;
+        let failed = match &result {
+            Ok(resp) => is_failure_status(resp.status()),
+            Err(err) => matches!(err, Error::Connection(_) | Error::Proxy(_)),
+        };
+        probe.done = true;
+        self.record(probe.probe, failed);
+        result
+    }
+
+    fn admit(&self) -> Result<bool> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        if let State::Open { until } = *state {
+            if now < until {
+                return Err(Error::CircuitOpen {
+                    retry_after: until - now,
+                });
+            }
+            *state = State::HalfOpen {
+                in_flight: 0,
+                successes: 0,
+            };
+        }
+        match &mut *state {
+            State::Closed { .. } => Ok(false),
+            State::HalfOpen { in_flight, .. } => {
+                if *in_flight >= self.options.half_open_probes.max(1) {
+                    return Err(Error::CircuitOpen {
+                        retry_after: Duration::ZERO,
+                    });
+                }
+                *in_flight += 1;
+                Ok(true)
+            }
+            State::Open { .. } => unreachable!(),
+        }
+    }
+
+    /// Free the slot of a half-open probe that ended without an outcome.
+    fn release(&self) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if let State::HalfOpen { in_flight, .. } = &mut *state {
+            *in_flight = in_flight.saturating_sub(1);
+        }
+    }
+
+    fn record(&self, probe: bool, failed: bool) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let open = State::Open {
+            until: Instant::now() + self.options.cool_down,
+        };
+        *state = match *state {
+            State::Closed { failures } if failed => {
+                let failures = failures + 1;
+                if failures >= self.options.failure_threshold.max(1) {
+                    open
+                } else {
+                    State::Closed { failures }
+                }
+            }
+            State::Closed { .. } => State::Closed { failures: 0 },
+            State::HalfOpen { .. } if probe && failed => open,
+            State::HalfOpen {
+                in_flight,
+                successes,
+            } if probe => {
+                let successes = successes + 1;
+                if successes >= self.options.half_open_probes.max(1) {
+                    State::Closed { failures: 0 }
+                } else {
+                    State::HalfOpen {
+                        in_flight: in_flight.saturating_sub(1),
+                        successes,
+                    }
+                }
+            }
+            other => other,
+        };
+    }
+}
+
+/// Releases a half-open probe slot when the call is dropped before its outcome is recorded,
+/// e.g. by a deadline, a cancellation token or `tokio::select!`.
+struct Probe<'a> {
+    breaker: &'a Breaker,
+    probe: bool,
+    done: bool,
+}
+
+impl Drop for Probe<'_> {
+    fn drop(&mut self) {
+        if self.probe && !self.done {
+            self.breaker.release();
+        }
+    }
+}
+
+fn is_failure_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
@@ -0,0 +1,114 @@
+use crate::Result;
+use crate::client::Client;
+use futures::future::BoxFuture;
+use reqwest::{Request, Response};
+use std::fmt;
+use std::sync::Arc;
+
+/// A transport layer wrapped around every request the client sends, login included.
+///
+/// Call `next.run(request)` to pass the request on, or return early to short-circuit it.
+pub trait Middleware: fmt::Debug + Send + Sync {
+    fn handle<'a>(&'a self, request: Request, next: Next<'a>) -> BoxFuture<'a, Result<Response>>;
+}
+
+/// One entry of the middleware stack in [`ClientOptions::layers`](crate::ClientOptions::layers),
+/// listed from outermost to innermost.
+#[derive(Debug, Clone)]
+pub enum Layer {
+    /// Attaches the current bearer token as an `Authorization` header.
+    Auth,
+    /// Retries the rest of the stack according to the `retry_*` options.
+    Retry,
+    /// Fails fast while the configured circuit breaker is open.
+    CircuitBreaker,
+    /// Waits for the configured rate limiter before each attempt.
+    RateLimit,
+    /// Applies `request_timeout`, or the per-call timeout and deadline, to each attempt.
+    /// Without it, only `request_timeout` applies.
+    Timeout,
+    Custom(Arc<dyn Middleware>),
+}
+
+impl Layer {
+    pub fn custom(middleware: impl Middleware + 'static) -> Self {
+        Layer::Custom(Arc::new(middleware))
+    }
+
+    /// The built-in stack: `Auth`, `Retry`, `CircuitBreaker`, `RateLimit`, then `Timeout`.
+    pub fn default_stack() -> Vec<Layer> {
+        vec![
+            Layer::Auth,
+            Layer::Retry,
+            Layer::CircuitBreaker,
+            Layer::RateLimit,
+            Layer::Timeout,
+        ]
+    }
+}
+
+/// The remainder of the stack below the current layer.
+#[derive(Clone, Copy)]
+pub struct Next<'a> {
+    client: &'a Client,
+    endpoint: &'static str,
+    layers: &'a [Layer],
+}
+
+impl<'a> Next<'a> {
+    pub(crate) fn new(client: &'a Client, endpoint: &'static str, layers: &'a [Layer]) -> Self {
+        Self {
+            client,
+            endpoint,
+            layers,
+        }
+    }
+
+    /// Name of the client method that issued the request, e.g. `get_inbounds_list`.
+    pub fn endpoint(&self) -> &'static str {
+        self.endpoint
+    }
+
+    /// Run the request through the remaining layers and the HTTP transport.
+    pub fn run(self, request: Request) -> BoxFuture<'a, Result<Response>> {
+        Box::pin(async move {
+            let Some((layer, layers)) = self.layers.split_first() else {
+                return self.client.execute(request).await;
+            };
+            let next = Next { layers, ..self };
+            match layer {
+                Layer::Auth => next.run(self.client.authorize(request)).await,
+                Layer::Retry => self.client.retry(request, next).await,
+                Layer::CircuitBreaker => match self.client.breaker() {
+                    Some(breaker) => breaker.call(request, next).await,
+                    None => next.run(request).await,
+                },
+                Layer::RateLimit => {
+                    if let Some(bucket) = self.client.rate_limiter() {
+                        bucket.acquire().await;
+                    }
+                    next.run(request).await
+                }
+                Layer::Timeout => {
+                    let mut request = request;
+                    request
+                        .timeout_mut()
+                        .get_or_insert(self.client.attempt_timeout());
+                    next.run(request)
+                        .await
+                        .map_err(|e| self.client.deadline_error(e))
+                }
+                Layer::Custom(middleware) => middleware.handle(request, next).await,
+            }
+        })
+    }
+}
+
+impl fmt::Debug for Next<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Next")
+            .field("endpoint", &self.endpoint)
+            .field("layers", &self.layers)
+            .finish()
+    }
+}
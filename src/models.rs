@@ -1,8 +1,9 @@
 use crate::inbounds::{InboundProtocols, SniffingOption, TransportProtocol};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
-use serde_with::{json::JsonString, serde_as};
+use serde_with::{json::JsonString, serde_as, skip_serializing_none};
 use std::collections::BTreeMap;
 use std::ops::Not;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct Response<T> {
@@ -23,6 +24,15 @@ impl<T> Response<T> {
     }
 
     pub fn into_result(self) -> crate::Result<T> {
+        self.into_result_for("unknown", reqwest::StatusCode::OK)
+    }
+
+    /// Like [`into_result`](Self::into_result), attributing failures to `endpoint`.
+    pub fn into_result_for(
+        self,
+        endpoint: &'static str,
+        status: reqwest::StatusCode,
+    ) -> crate::Result<T> {
         if self.success {
             Ok(self.object)
         } else {
@@ -31,7 +41,9 @@ impl<T> Response<T> {
             } else {
                 self.message
             };
-            Err(crate::error::Error::ApiError { message: msg })
+            Err(crate::error::Error::from_panel_message(
+                endpoint, status, msg,
+            ))
         }
     }
 }
@@ -58,6 +70,19 @@ pub struct ClientStats {
     pub last_online: Option<i64>,
 }
 
+impl ClientStats {
+    /// When the client was last seen, or `None` if it never connected.
+    pub fn last_online_at(&self) -> Option<SystemTime> {
+        self.last_online.and_then(from_unix_millis)
+    }
+}
+
+/// Convert a panel timestamp in Unix milliseconds; `0` and negative values mean "never".
+pub(crate) fn from_unix_millis(millis: i64) -> Option<SystemTime> {
+    let millis = u64::try_from(millis).ok().filter(|&m| m > 0)?;
+    Some(UNIX_EPOCH + Duration::from_millis(millis))
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Inbounds {
     pub id: u64,
@@ -592,6 +617,7 @@ pub struct EchCert {
     pub ech_server_keys: Option<String>,
 }
 
+#[skip_serializing_none]
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ConfigJson {
     pub api: Option<ConfigApi>,
@@ -604,13 +630,17 @@ pub struct ConfigJson {
     pub extra: BTreeMap<String, serde_json::Value>,
 }
 
+#[skip_serializing_none]
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ConfigApi {
     #[serde(default)]
     pub services: Vec<String>,
     pub tag: Option<String>,
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
 }
 
+#[skip_serializing_none]
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct ConfigInbound {
@@ -622,15 +652,21 @@ pub struct ConfigInbound {
     #[serde(rename = "streamSettings")]
     pub stream_settings: Option<serde_json::Value>,
     pub tag: Option<String>,
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
 }
 
+#[skip_serializing_none]
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ConfigOutbound {
     pub protocol: Option<String>,
     pub settings: Option<serde_json::Value>,
     pub tag: Option<String>,
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
 }
 
+#[skip_serializing_none]
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct ConfigLog {
@@ -639,22 +675,32 @@ pub struct ConfigLog {
     pub error: Option<String>,
     pub loglevel: Option<String>,
     pub mask_address: Option<String>,
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
 }
 
+#[skip_serializing_none]
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ConfigMetrics {
     pub listen: Option<String>,
     pub tag: Option<String>,
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
 }
 
+#[skip_serializing_none]
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct ConfigRouting {
-    pub domain_strategy: Option<crate::inbounds::DomainStrategyOption>,
+    /// Kept as text so values such as `IPIfNonMatch` survive a round trip unchanged.
+    pub domain_strategy: Option<String>,
     #[serde(default)]
     pub rules: Vec<RoutingRule>,
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
 }
 
+#[skip_serializing_none]
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct RoutingRule {
     #[serde(rename = "type")]
@@ -670,3 +716,152 @@ pub struct RoutingRule {
     #[serde(flatten)]
     pub extra: BTreeMap<String, serde_json::Value>,
 }
+
+/// Traffic counters for one outbound, keyed by the `tag` of a [`ConfigOutbound`].
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OutboundTraffic {
+    pub id: u64,
+    pub tag: String,
+    pub up: u128,
+    pub down: u128,
+    pub total: u128,
+}
+
+/// WARP account credentials the panel stores after registration.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct WarpData {
+    pub access_token: String,
+    pub device_id: String,
+    pub license_key: String,
+    /// WireGuard private key for the WARP outbound.
+    pub private_key: String,
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+/// Result of registering a WARP device.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WarpRegistration {
+    pub data: WarpData,
+    pub config: WarpDevice,
+}
+
+/// A WARP device as Cloudflare reports it.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WarpDevice {
+    pub id: String,
+    /// WireGuard public key registered for the device.
+    #[serde(default)]
+    pub key: String,
+    pub account: Option<WarpAccount>,
+    pub config: WarpConfig,
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WarpAccount {
+    pub id: String,
+    /// `free`, `limited` or `unlimited`.
+    pub account_type: Option<String>,
+    pub warp_plus: Option<bool>,
+    pub premium_data: Option<u64>,
+    pub quota: Option<u64>,
+    pub license: Option<String>,
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+/// WireGuard parameters for a WARP outbound.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WarpConfig {
+    /// Base64 client id; its bytes are the WireGuard `reserved` field.
+    pub client_id: String,
+    pub peers: Vec<WarpPeer>,
+    pub interface: WarpInterface,
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WarpPeer {
+    pub public_key: String,
+    pub endpoint: WarpEndpoint,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WarpEndpoint {
+    pub v4: Option<String>,
+    pub v6: Option<String>,
+    pub host: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WarpInterface {
+    pub addresses: WarpAddresses,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WarpAddresses {
+    pub v4: String,
+    pub v6: String,
+}
+
+/// Body of `/panel/xray/`, which older panels send as a JSON-encoded string.
+#[derive(Debug, Deserialize)]
+pub(crate) struct XraySetting {
+    #[serde(rename = "xraySetting")]
+    pub xray_setting: ConfigJson,
+}
+
+/// Panel settings from `/panel/setting/all`.
+///
+/// `update_settings` replaces every setting, so modify a value read from the panel rather
+/// than building one from `Default`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AllSetting {
+    pub web_listen: String,
+    pub web_domain: String,
+    pub web_port: u16,
+    pub web_cert_file: String,
+    pub web_key_file: String,
+    pub web_base_path: String,
+    /// Session lifetime in minutes.
+    pub session_max_age: i64,
+    pub tg_bot_enable: bool,
+    pub tg_bot_token: String,
+    pub tg_bot_proxy: String,
+    #[serde(rename = "tgBotAPIServer")]
+    pub tg_bot_api_server: String,
+    pub tg_bot_chat_id: String,
+    /// Cron spec for periodic bot reports.
+    pub tg_run_time: String,
+    pub tg_bot_backup: bool,
+    pub tg_bot_login_notify: bool,
+    pub tg_cpu: i64,
+    pub tg_lang: String,
+    /// IANA time zone, e.g. `Asia/Tehran`.
+    pub time_location: String,
+    pub sub_enable: bool,
+    pub sub_json_enable: bool,
+    pub sub_title: String,
+    pub sub_listen: String,
+    pub sub_port: u16,
+    pub sub_path: String,
+    pub sub_domain: String,
+    pub sub_cert_file: String,
+    pub sub_key_file: String,
+    /// Client refresh interval in hours.
+    pub sub_updates: i64,
+    pub sub_encrypt: bool,
+    pub sub_show_info: bool,
+    #[serde(rename = "subURI")]
+    pub sub_uri: String,
+    pub sub_json_path: String,
+    #[serde(rename = "subJsonURI")]
+    pub sub_json_uri: String,
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
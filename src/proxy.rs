@@ -0,0 +1,101 @@
+use crate::Result;
+use crate::credentials::SecretString;
+use crate::error::Error;
+use reqwest::{ClientBuilder, NoProxy, Proxy, Url};
+use std::fmt;
+
+/// Outbound proxy used for every request to the panel, including login.
+#[derive(Clone)]
+pub struct ProxyOptions {
+    /// `http://host:port` for HTTP CONNECT proxies, `socks5://host:port` (local DNS)
+    /// or `socks5h://host:port` (DNS resolved by the proxy).
+    pub url: String,
+    pub username: Option<String>,
+    pub password: Option<SecretString>,
+    /// Hosts reached directly: exact names, domain suffixes (`.example.com`), IPs or `*`.
+    pub no_proxy: Vec<String>,
+}
+
+impl ProxyOptions {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            username: None,
+            password: None,
+            no_proxy: Vec::new(),
+        }
+    }
+
+    pub fn with_auth(
+        mut self,
+        username: impl Into<String>,
+        password: impl Into<SecretString>,
+    ) -> Self {
+        self.username = Some(username.into());
+        self.password = Some(password.into());
+        self
+    }
+
+    pub fn with_no_proxy(mut self, hosts: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.no_proxy = hosts.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Whether requests to `host` skip the proxy.
+    pub fn bypasses(&self, host: &str) -> bool {
+        self.no_proxy.iter().any(|entry| {
+            let entry = entry.trim();
+            let domain = entry.trim_start_matches('.');
+            entry == "*"
+                || host.eq_ignore_ascii_case(domain)
+                || host
+                    .to_ascii_lowercase()
+                    .ends_with(&format!(".{}", domain.to_ascii_lowercase()))
+        })
+    }
+}
+
+impl fmt::Debug for ProxyOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // `Proxy::all` accepts `user:pass@` in the URL, so never print the userinfo.
+        let url = match Url::parse(&self.url) {
+            Ok(mut url) => {
+                let _ = url.set_username("");
+                let _ = url.set_password(None);
+                url.to_string()
+            }
+            Err(_) => "<unparsable>".to_string(),
+        };
+        f.debug_struct("ProxyOptions")
+            .field("url", &url)
+            .field("username", &self.username)
+            .field("password", &self.password.as_ref().map(|_| "<redacted>"))
+            .field("no_proxy", &self.no_proxy)
+            .finish()
+    }
+}
+
+pub(crate) fn apply(builder: ClientBuilder, proxy: Option<&ProxyOptions>) -> Result<ClientBuilder> {
+    let Some(options) = proxy else {
+        return Ok(builder);
+    };
+    let mut url = Url::parse(&options.url)
+        .map_err(|e| Error::InvalidUrl(format!("invalid proxy URL `{}`: {e}", options.url)))?;
+    if !matches!(url.scheme(), "http" | "https" | "socks5" | "socks5h") {
+        return Err(Error::InvalidUrl(format!(
+            "unsupported proxy scheme `{}`, expected http, https, socks5 or socks5h",
+            url.scheme()
+        )));
+    }
+    if let Some(username) = &options.username {
+        url.set_username(username)
+            .and_then(|_| {
+                url.set_password(options.password.as_ref().map(SecretString::expose_secret))
+            })
+            .map_err(|_| Error::InvalidUrl("proxy URL cannot carry credentials".into()))?;
+    }
+    let proxy = Proxy::all(url.as_str())
+        .map_err(|e| Error::InvalidUrl(format!("invalid proxy URL: {e}")))?
+        .no_proxy(NoProxy::from_string(&options.no_proxy.join(",")));
+    Ok(builder.proxy(proxy))
+}
@@ -0,0 +1,165 @@
+use crate::client::ClientOptions;
+use crate::error::Error;
+use reqwest::header::{HeaderValue, RETRY_AFTER};
+use reqwest::{Method, Response, StatusCode};
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::BuildHasher;
+use std::time::SystemTime;
+use tokio::time::Duration;
+
+/// The call a retry decision is made for.
+#[derive(Debug, Clone, Copy)]
+pub struct Endpoint<'a> {
+    /// Name of the client method, e.g. `online_clients`, or `login`.
+    pub name: &'static str,
+    pub method: &'a Method,
+    /// Per-call retry count from [`CallOptions`](crate::CallOptions), if set.
+    pub retry_count: Option<u32>,
+}
+
+/// Result of one attempt.
+#[derive(Debug, Clone, Copy)]
+pub enum Outcome<'a> {
+    Response(&'a Response),
+    Error(&'a Error),
+}
+
+/// Decides whether and when a failed attempt is retried.
+pub trait RetryPolicy: fmt::Debug + Send + Sync {
+    /// Delay before the next attempt, or `None` to return `outcome` to the caller.
+    ///
+    /// `attempt` counts the attempts made so far, starting at 1.
+    fn retry(
+        &self,
+        endpoint: &Endpoint<'_>,
+        attempt: u32,
+        outcome: Outcome<'_>,
+    ) -> Option<Duration>;
+}
+
+/// Randomization applied to the exponential backoff delay.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Jitter {
+    #[default]
+    None,
+    /// Uniform in `[0, delay]`.
+    Full,
+    /// `delay / 2` plus uniform in `[0, delay / 2]`.
+    Equal,
+}
+
+impl Jitter {
+    pub fn apply(self, delay: Duration) -> Duration {
+        match self {
+            Jitter::None => delay,
+            Jitter::Full => delay.mul_f64(random_unit()),
+            Jitter::Equal => delay / 2 + (delay / 2).mul_f64(random_unit()),
+        }
+    }
+}
+
+fn random_unit() -> f64 {
+    let bits = RandomState::new().hash_one(SystemTime::now());
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Default policy: retries transport errors, `429` and `5xx` for idempotent methods and
+/// endpoints marked safe, with capped exponential backoff. `Retry-After` takes precedence.
+#[derive(Debug, Clone)]
+pub struct ExponentialBackoff {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    pub jitter: Jitter,
+    pub methods: Vec<Method>,
+    /// Endpoints retried regardless of method, e.g. read-only POSTs like `online_clients`.
+    pub endpoints: Vec<String>,
+}
+
+impl Default for ExponentialBackoff {
+    fn default() -> Self {
+        Self::from_options(&ClientOptions::default())
+    }
+}
+
+impl ExponentialBackoff {
+    pub fn from_options(options: &ClientOptions) -> Self {
+        Self {
+            max_retries: options.retry_count,
+            base_delay: options.retry_base_delay,
+            max_delay: options.retry_max_delay,
+            jitter: options.retry_jitter,
+            methods: options.retry_methods.clone(),
+            endpoints: options.retry_endpoints.clone(),
+        }
+    }
+
+    pub fn with_jitter(mut self, jitter: Jitter) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Treat `endpoint` as safe to retry even when it uses a non-idempotent method.
+    pub fn retry_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.endpoints.push(endpoint.into());
+        self
+    }
+
+    pub fn is_retryable(&self, endpoint: &Endpoint<'_>) -> bool {
+        self.methods.contains(endpoint.method) || self.endpoints.iter().any(|e| e == endpoint.name)
+    }
+
+    /// Backoff delay after `attempt` attempts, before jitter.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 1u32
+            .checked_shl(attempt.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+        self.base_delay.saturating_mul(factor).min(self.max_delay)
+    }
+}
+
+impl RetryPolicy for ExponentialBackoff {
+    fn retry(
+        &self,
+        endpoint: &Endpoint<'_>,
+        attempt: u32,
+        outcome: Outcome<'_>,
+    ) -> Option<Duration> {
+        let max_retries = endpoint.retry_count.unwrap_or(self.max_retries);
+        if attempt > max_retries || !self.is_retryable(endpoint) {
+            return None;
+        }
+        match outcome {
+            Outcome::Response(resp) => {
+                let status = resp.status();
+                if status != StatusCode::TOO_MANY_REQUESTS && !status.is_server_error() {
+                    return None;
+                }
+                Some(
+                    retry_after(resp.headers().get(RETRY_AFTER))
+                        .unwrap_or_else(|| self.jitter.apply(self.backoff(attempt))),
+                )
+            }
+            Outcome::Error(Error::Connection(e) | Error::Proxy(e))
+                if e.is_timeout() || e.is_connect() =>
+            {
+                Some(self.jitter.apply(self.backoff(attempt)))
+            }
+            Outcome::Error(_) => None,
+        }
+    }
+}
+
+/// Parse a `Retry-After` header given either in seconds or as an HTTP date.
+pub fn retry_after(value: Option<&HeaderValue>) -> Option<Duration> {
+    let value = value?.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let at = httpdate::parse_http_date(value).ok()?;
+    Some(
+        at.duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO),
+    )
+}
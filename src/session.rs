@@ -0,0 +1,154 @@
+use crate::Result;
+use crate::client::AuthToken;
+use crate::error::Error;
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+use tokio::time::Duration;
+
+/// Panel session persisted between process runs.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct StoredSession {
+    /// `Cookie` header value for the panel URL.
+    pub cookie: Option<String>,
+    pub token: Option<AuthToken>,
+    /// Unix timestamp (seconds) after which the session is considered stale.
+    pub expires_at: Option<i64>,
+}
+
+impl StoredSession {
+    /// Whether the session expires within `margin` from now.
+    pub fn expires_within(&self, margin: Duration) -> bool {
+        match self.expires_at {
+            Some(expires_at) => unix_now().saturating_add(margin.as_secs() as i64) >= expires_at,
+            None => false,
+        }
+    }
+}
+
+impl fmt::Debug for StoredSession {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StoredSession")
+            .field("cookie", &self.cookie.as_ref().map(|_| "<redacted>"))
+            .field("token", &self.token)
+            .field("expires_at", &self.expires_at)
+            .finish()
+    }
+}
+
+/// Storage for panel sessions, keyed by panel URL and username.
+pub trait SessionStore: fmt::Debug + Send + Sync {
+    fn load<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Option<StoredSession>>>;
+    fn save<'a>(&'a self, key: &'a str, session: &'a StoredSession) -> BoxFuture<'a, Result<()>>;
+    fn remove<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<()>>;
+}
+
+/// Stores sessions as a JSON map in a single file, readable only by the owner on Unix.
+#[derive(Debug)]
+pub struct FileSessionStore {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl FileSessionStore {
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    async fn read_all(&self) -> Result<BTreeMap<String, StoredSession>> {
+        match tokio::fs::read(&self.path).await {
+            Ok(bytes) if bytes.is_empty() => Ok(BTreeMap::new()),
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .map_err(|e| Error::OtherError(format!("session store decode: {e}"))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+            Err(e) => Err(io_error(e)),
+        }
+    }
+
+    async fn write_all(&self, sessions: &BTreeMap<String, StoredSession>) -> Result<()> {
+        let json = serde_json::to_vec_pretty(sessions)
+            .map_err(|e| Error::OtherError(format!("session store encode: {e}")))?;
+        if let Some(parent) = self.path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            tokio::fs::create_dir_all(parent).await.map_err(io_error)?;
+        }
+        let tmp = self.path.with_extension("tmp");
+        // A leftover temp file would keep its old mode, so start from a fresh one.
+        match tokio::fs::remove_file(&tmp).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(io_error(e)),
+            _ => {}
+        }
+        let mut options = tokio::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        // Owner-only from creation, so the cookies are never readable by others.
+        #[cfg(unix)]
+        options.mode(0o600);
+        let mut file = options.open(&tmp).await.map_err(io_error)?;
+        file.write_all(&json).await.map_err(io_error)?;
+        file.sync_all().await.map_err(io_error)?;
+        drop(file);
+        tokio::fs::rename(&tmp, &self.path).await.map_err(io_error)
+    }
+}
+
+impl SessionStore for FileSessionStore {
+    fn load<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Option<StoredSession>>> {
+        Box::pin(async move {
+            let _guard = self.lock.lock().await;
+            Ok(self.read_all().await?.remove(key))
+        })
+    }
+
+    fn save<'a>(&'a self, key: &'a str, session: &'a StoredSession) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let _guard = self.lock.lock().await;
+            let mut sessions = self.read_all().await?;
+            sessions.insert(key.to_string(), session.clone());
+            self.write_all(&sessions).await
+        })
+    }
+
+    fn remove<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let _guard = self.lock.lock().await;
+            let mut sessions = self.read_all().await?;
+            if sessions.remove(key).is_some() {
+                self.write_all(&sessions).await?;
+            }
+            Ok(())
+        })
+    }
+}
+
+fn io_error(e: std::io::Error) -> Error {
+    Error::OtherError(format!("session store: {e}"))
+}
+
+pub(crate) fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Normalize a panel timestamp that may be in seconds or milliseconds to seconds.
+pub(crate) fn unix_secs(ts: i64) -> i64 {
+    if ts > 1_000_000_000_000 {
+        ts / 1000
+    } else {
+        ts
+    }
+}
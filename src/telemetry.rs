@@ -0,0 +1,108 @@
+use crate::Result;
+use sha2::{Digest, Sha256};
+use std::future::Future;
+
+/// How client emails are written into tracing spans.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EmailField {
+    Plain,
+    /// First 16 hex digits of the SHA-256 of the email, stable across calls.
+    Hashed,
+    #[default]
+    Redacted,
+}
+
+impl EmailField {
+    pub fn render(self, email: &str) -> String {
+        match self {
+            EmailField::Plain => email.to_string(),
+            EmailField::Hashed => {
+                let digest = Sha256::digest(email.as_bytes());
+                digest[..8].iter().map(|b| format!("{b:02x}")).collect()
+            }
+            EmailField::Redacted => "<redacted>".to_string(),
+        }
+    }
+}
+
+/// Identity of a public API call.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Call<'a> {
+    pub endpoint: &'static str,
+    pub inbound_id: Option<u64>,
+    pub email: Option<&'a str>,
+}
+
+impl<'a> Call<'a> {
+    pub fn new(endpoint: &'static str) -> Self {
+        Self {
+            endpoint,
+            inbound_id: None,
+            email: None,
+        }
+    }
+
+    pub fn inbound(mut self, inbound_id: u64) -> Self {
+        self.inbound_id = Some(inbound_id);
+        self
+    }
+
+    pub fn email(mut self, email: &'a str) -> Self {
+        self.email = Some(email);
+        self
+    }
+}
+
+/// Run `fut` inside a `panel_api` span for `call` and record its latency.
+#[cfg(feature = "tracing")]
+pub(crate) async fn traced<T>(
+    call: Call<'_>,
+    emails: EmailField,
+    fut: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    use tracing::Instrument;
+    use tracing::field::Empty;
+
+    let span = tracing::info_span!(
+        "panel_api",
+        endpoint = call.endpoint,
+        http.method = Empty,
+        inbound_id = call.inbound_id,
+        email = call.email.map(|e| emails.render(e)),
+        attempt = Empty,
+        status = Empty,
+        latency_ms = Empty,
+    );
+    let started = std::time::Instant::now();
+    let result = fut.instrument(span.clone()).await;
+    span.record("latency_ms", started.elapsed().as_millis() as u64);
+    if let Err(err) = &result {
+        span.in_scope(|| tracing::debug!(error = %err, "panel call failed"));
+    }
+    result
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) async fn traced<T>(
+    call: Call<'_>,
+    _emails: EmailField,
+    fut: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    log::debug!("panel call {} inbound={:?}", call.endpoint, call.inbound_id);
+    fut.await
+}
+
+pub(crate) fn record_method(_method: &reqwest::Method) {
+    #[cfg(feature = "tracing")]
+    tracing::Span::current().record("http.method", _method.as_str());
+}
+
+pub(crate) fn record_attempt(_attempt: u32) {
+    #[cfg(feature = "tracing")]
+    tracing::Span::current().record("attempt", _attempt);
+}
+
+pub(crate) fn record_status(_status: reqwest::StatusCode) {
+    #[cfg(feature = "tracing")]
+    tracing::Span::current().record("status", _status.as_u16());
+}
@@ -0,0 +1,172 @@
+use crate::Result;
+use crate::error::Error;
+use reqwest::{Certificate, ClientBuilder, Identity};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{CryptoProvider, verify_tls12_signature, verify_tls13_signature};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+use rustls::{DigitallySignedStruct, SignatureScheme};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::sync::Arc;
+
+/// TLS trust configuration for reaching the panel.
+#[derive(Clone, Default)]
+pub struct TlsOptions {
+    /// Extra trusted root certificates, PEM encoded.
+    pub root_ca_pems: Vec<Vec<u8>>,
+    /// Accept any server certificate. Only for testing; prefer `pinned_sha256`.
+    pub accept_invalid_certs: bool,
+    /// Client certificate chain followed by its private key, PEM encoded (mTLS).
+    pub client_identity_pem: Option<Vec<u8>>,
+    /// SHA-256 fingerprints of accepted server certificates as hex, colons optional.
+    /// When set, the leaf certificate must match one of them and replaces chain validation.
+    pub pinned_sha256: Vec<String>,
+}
+
+impl fmt::Debug for TlsOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TlsOptions")
+            .field("root_ca_pems", &self.root_ca_pems.len())
+            .field("accept_invalid_certs", &self.accept_invalid_certs)
+            .field(
+                "client_identity_pem",
+                &self.client_identity_pem.as_ref().map(|_| "<redacted>"),
+            )
+            .field("pinned_sha256", &self.pinned_sha256)
+            .finish()
+    }
+}
+
+/// SHA-256 fingerprint of a DER encoded certificate, as lowercase hex.
+pub fn cert_fingerprint_sha256(der: &[u8]) -> String {
+    Sha256::digest(der)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+pub(crate) fn apply(builder: ClientBuilder, tls: &TlsOptions) -> Result<ClientBuilder> {
+    if !tls.pinned_sha256.is_empty() {
+        return Ok(builder.use_preconfigured_tls(pinned_config(tls)?));
+    }
+
+    let mut builder = builder.danger_accept_invalid_certs(tls.accept_invalid_certs);
+    for pem in &tls.root_ca_pems {
+        for cert in Certificate::from_pem_bundle(pem)
+            .map_err(|e| Error::Tls(format!("invalid root CA PEM: {e}")))?
+        {
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+    if let Some(pem) = &tls.client_identity_pem {
+        let identity = Identity::from_pem(pem)
+            .map_err(|e| Error::Tls(format!("invalid client identity PEM: {e}")))?;
+        builder = builder.identity(identity);
+    }
+    Ok(builder)
+}
+
+fn pinned_config(tls: &TlsOptions) -> Result<rustls::ClientConfig> {
+    let pins = tls
+        .pinned_sha256
+        .iter()
+        .map(|pin| parse_pin(pin))
+        .collect::<Result<Vec<_>>>()?;
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let verifier = PinnedCertVerifier {
+        pins,
+        provider: provider.clone(),
+    };
+    let builder = rustls::ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(|e| Error::Tls(e.to_string()))?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(verifier));
+    match &tls.client_identity_pem {
+        Some(pem) => {
+            let certs = CertificateDer::pem_slice_iter(pem)
+                .collect::<std::result::Result<Vec<_>, _>>()
+                .map_err(|e| Error::Tls(format!("invalid client certificate PEM: {e}")))?;
+            let key = PrivateKeyDer::from_pem_slice(pem)
+                .map_err(|e| Error::Tls(format!("invalid client key PEM: {e}")))?;
+            builder
+                .with_client_auth_cert(certs, key)
+                .map_err(|e| Error::Tls(e.to_string()))
+        }
+        None => Ok(builder.with_no_client_auth()),
+    }
+}
+
+fn parse_pin(pin: &str) -> Result<[u8; 32]> {
+    let hex: String = pin.chars().filter(|c| *c != ':').collect();
+    let invalid = || Error::Tls(format!("invalid SHA-256 pin `{pin}`"));
+    if hex.len() != 64 || !hex.is_ascii() {
+        return Err(invalid());
+    }
+    let mut out = [0u8; 32];
+    for (i, byte) in out.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).map_err(|_| invalid())?;
+    }
+    Ok(out)
+}
+
+#[derive(Debug)]
+struct PinnedCertVerifier {
+    pins: Vec<[u8; 32]>,
+    provider: Arc<CryptoProvider>,
+}
+
+impl ServerCertVerifier for PinnedCertVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> std::result::Result<ServerCertVerified, rustls::Error> {
+        let fingerprint: [u8; 32] = Sha256::digest(end_entity.as_ref()).into();
+        if self.pins.contains(&fingerprint) {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::General(
+                "server certificate does not match any pinned SHA-256 fingerprint".into(),
+            ))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
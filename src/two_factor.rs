@@ -0,0 +1,96 @@
+use crate::Result;
+use crate::credentials::SecretString;
+use crate::error::Error;
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const TOTP_STEP_SECS: u64 = 30;
+const TOTP_DIGITS: u32 = 6;
+
+/// Source of the code sent as `twoFactorCode` on login.
+#[derive(Clone)]
+pub enum TwoFactor {
+    /// Fixed code, e.g. typed in by an operator.
+    Code(SecretString),
+    /// Called on every login to obtain a fresh code.
+    Callback(Arc<dyn Fn() -> String + Send + Sync>),
+    /// Base32 TOTP secret, codes are generated locally (RFC 6238, SHA-1, 30s, 6 digits).
+    TotpSecret(SecretString),
+}
+
+impl TwoFactor {
+    /// Wrap a closure returning the current code.
+    pub fn callback(f: impl Fn() -> String + Send + Sync + 'static) -> Self {
+        Self::Callback(Arc::new(f))
+    }
+
+    /// Produce the code for the current login attempt.
+    pub fn code(&self) -> Result<SecretString> {
+        match self {
+            TwoFactor::Code(code) => Ok(code.clone()),
+            TwoFactor::Callback(f) => Ok(f().into()),
+            TwoFactor::TotpSecret(secret) => {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_err(|e| Error::OtherError(format!("system time: {e}")))?
+                    .as_secs();
+                totp(secret.expose_secret(), now).map(SecretString::from)
+            }
+        }
+    }
+}
+
+impl fmt::Debug for TwoFactor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TwoFactor::Code(_) => f.write_str("Code(<redacted>)"),
+            TwoFactor::Callback(_) => f.write_str("Callback(..)"),
+            TwoFactor::TotpSecret(_) => f.write_str("TotpSecret(<redacted>)"),
+        }
+    }
+}
+
+/// Generate a TOTP code for a base32 secret at the given unix time.
+pub fn totp(secret: &str, unix_time: u64) -> Result<String> {
+    let key =
+        decode_base32(secret).ok_or_else(|| Error::OtherError("invalid TOTP secret".into()))?;
+    let counter = unix_time / TOTP_STEP_SECS;
+    let mut mac = Hmac::<Sha1>::new_from_slice(&key)
+        .map_err(|e| Error::OtherError(format!("invalid TOTP secret: {e}")))?;
+    mac.update(&counter.to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        digest[offset] & 0x7f,
+        digest[offset + 1],
+        digest[offset + 2],
+        digest[offset + 3],
+    ]);
+    let code = binary % 10u32.pow(TOTP_DIGITS);
+    Ok(format!("{:0width$}", code, width = TOTP_DIGITS as usize))
+}
+
+fn decode_base32(input: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(input.len() * 5 / 8);
+    let mut buffer = 0u64;
+    let mut bits = 0u32;
+    for c in input.chars() {
+        let value = match c.to_ascii_uppercase() {
+            c @ 'A'..='Z' => c as u64 - 'A' as u64,
+            c @ '2'..='7' => c as u64 - '2' as u64 + 26,
+            '=' | ' ' | '-' => continue,
+            _ => return None,
+        };
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    if out.is_empty() { None } else { Some(out) }
+}
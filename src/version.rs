@@ -0,0 +1,100 @@
+use crate::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// A 3x-ui release, e.g. `2.6.3`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PanelVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl PanelVersion {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+}
+
+impl fmt::Display for PanelVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+impl FromStr for PanelVersion {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::OtherError(format!("invalid panel version `{s}`"));
+        let mut parts = s.trim().trim_start_matches('v').split('.');
+        let mut next = || -> Result<u32, Error> {
+            parts
+                .next()
+                .ok_or_else(invalid)?
+                .parse()
+                .map_err(|_| invalid())
+        };
+        let version = Self::new(next()?, next()?, next()?);
+        if parts.next().is_some() {
+            return Err(invalid());
+        }
+        Ok(version)
+    }
+}
+
+/// Minimum panel version for endpoints that older panels do not serve, by client method name.
+pub const MIN_VERSIONS: &[(&str, PanelVersion)] = &[
+    ("get_new_mldsa65", PanelVersion::new(2, 6, 3)),
+    ("get_new_ech_cert", PanelVersion::new(2, 6, 3)),
+    ("get_new_mlkem768", PanelVersion::new(2, 8, 0)),
+    ("get_new_vless_enc", PanelVersion::new(2, 8, 0)),
+];
+
+/// What the connected panel supports, derived from its version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+    /// `None` when the version could not be detected; every endpoint is then attempted.
+    pub version: Option<PanelVersion>,
+}
+
+impl Capabilities {
+    /// Minimum version `endpoint` needs, if it is gated at all.
+    pub fn min_version(endpoint: &str) -> Option<PanelVersion> {
+        MIN_VERSIONS
+            .iter()
+            .find(|(name, _)| *name == endpoint)
+            .map(|(_, version)| *version)
+    }
+
+    pub fn supports(&self, endpoint: &str) -> bool {
+        match (self.version, Self::min_version(endpoint)) {
+            (Some(version), Some(min)) => version >= min,
+            _ => true,
+        }
+    }
+
+    /// Fail with [`Error::Unsupported`] if `endpoint` needs a newer panel.
+    pub fn check(&self, endpoint: &'static str) -> Result<(), Error> {
+        match Self::min_version(endpoint) {
+            Some(min_version) if !self.supports(endpoint) => Err(Error::Unsupported {
+                endpoint,
+                min_version,
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Find the panel version in a page, where 3x-ui appends it to asset URLs (`app.js?2.6.3`).
+pub(crate) fn version_from_html(html: &str) -> Option<PanelVersion> {
+    html.match_indices('?').find_map(|(i, _)| {
+        let rest = html[i + 1..].strip_prefix("v=").unwrap_or(&html[i + 1..]);
+        let end = rest.find(['"', '\'', '&']).unwrap_or(rest.len());
+        rest[..end].parse().ok()
+    })
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use common::{MockResponse, ok, serve};
+use rustix3::error::Error;
+use rustix3::{Client, ClientOptions};
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+
+async fn recording_panel() -> (String, Arc<Mutex<Vec<String>>>) {
+    let paths = Arc::new(Mutex::new(Vec::new()));
+    let seen = paths.clone();
+    let addr = serve(move |req| {
+        seen.lock().unwrap().push(req.path.clone());
+        if req.path.ends_with("/login") {
+            ok(json!(null))
+        } else if req.path.ends_with("/panel/api/inbounds/list") {
+            ok(json!([]))
+        } else {
+            MockResponse::status(404)
+        }
+    })
+    .await;
+    (format!("http://{addr}"), paths)
+}
+
+#[tokio::test]
+async fn web_base_path_is_applied_to_every_url() {
+    let (base, paths) = recording_panel().await;
+    let options = ClientOptions {
+        web_base_path: Some("/secret/".into()),
+        ..ClientOptions::default()
+    };
+    let client = Client::new_with_options("admin", "admin", base, options)
+        .await
+        .unwrap();
+    client.get_inbounds_list().await.unwrap();
+    assert_eq!(
+        *paths.lock().unwrap(),
+        ["/secret/login", "/secret/panel/api/inbounds/list"]
+    );
+}
+
+#[tokio::test]
+async fn url_path_without_trailing_slash_is_kept() {
+    let (base, paths) = recording_panel().await;
+    let client = Client::new("admin", "admin", format!("{base}/secret"))
+        .await
+        .unwrap();
+    client.get_inbounds_list().await.unwrap();
+    assert_eq!(
+        *paths.lock().unwrap(),
+        ["/secret/login", "/secret/panel/api/inbounds/list"]
+    );
+}
+
+#[tokio::test]
+async fn invalid_base_urls_fail_before_login() {
+    let with_base_path = |path: &str| ClientOptions {
+        web_base_path: Some(path.into()),
+        ..ClientOptions::default()
+    };
+    let cases = [
+        ("ftp://127.0.0.1:1/", ClientOptions::default()),
+        ("http://127.0.0.1:1/?a=b", ClientOptions::default()),
+        ("http://127.0.0.1:1/secret/", with_base_path("other")),
+        ("http://127.0.0.1:1/", with_base_path("/a/../b")),
+    ];
+    for (url, options) in cases {
+        let err = Client::new_with_options("admin", "admin", url, options)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidUrl(_)), "{url}: {err:?}");
+    }
+}
@@ -0,0 +1,131 @@
+mod common;
+
+use common::{fail, ok, serve, token_builder};
+use futures::StreamExt;
+use futures::future::BoxFuture;
+use reqwest::{Request, Response};
+use rustix3::{BatchMode, Layer, Middleware, Next};
+use serde_json::json;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::time::{Duration, sleep};
+
+#[derive(Debug, Default)]
+struct Gauge {
+    current: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+impl Middleware for Gauge {
+    fn handle<'a>(
+        &'a self,
+        request: Request,
+        next: Next<'a>,
+    ) -> BoxFuture<'a, rustix3::Result<Response>> {
+        Box::pin(async move {
+            let now = self.current.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(now, Ordering::SeqCst);
+            sleep(Duration::from_millis(20)).await;
+            let result = next.run(request).await;
+            self.current.fetch_sub(1, Ordering::SeqCst);
+            result
+        })
+    }
+}
+
+fn gauged(gauge: &Arc<Gauge>) -> Vec<Layer> {
+    let mut layers = Layer::default_stack();
+    layers.push(Layer::Custom(gauge.clone()));
+    layers
+}
+
+async fn panel() -> SocketAddr {
+    serve(|req| {
+        let email = req.path.rsplit('/').next().unwrap().to_string();
+        if email == "missing" {
+            return fail("client not found");
+        }
+        ok(json!({
+            "id": 1, "inboundId": 1, "enable": true, "email": email, "up": 1, "down": 2,
+            "expiryTime": 0, "total": 0, "reset": 0
+        }))
+    })
+    .await
+}
+
+#[tokio::test]
+async fn batch_respects_in_flight_limit() {
+    let gauge = Arc::new(Gauge::default());
+    let addr = panel().await;
+    let client = token_builder(addr)
+        .max_in_flight(3)
+        .layers(gauged(&gauge))
+        .build()
+        .unwrap();
+
+    let emails: Vec<String> = (0..12).map(|i| format!("user{i}")).collect();
+    let results: Vec<_> = client
+        .get_client_traffic_batch(emails.clone(), BatchMode::Continue)
+        .collect()
+        .await;
+
+    assert_eq!(results.len(), 12);
+    for (email, stats) in &results {
+        assert_eq!(&stats.as_ref().unwrap().email, email);
+    }
+    assert_eq!(gauge.peak.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn in_flight_limit_applies_to_concurrent_calls() {
+    let gauge = Arc::new(Gauge::default());
+    let addr = panel().await;
+    let client = token_builder(addr)
+        .max_in_flight(2)
+        .layers(gauged(&gauge))
+        .build()
+        .unwrap();
+
+    let calls = (0..6).map(|i| client.get_client_traffic_by_email(format!("user{i}")));
+    for result in futures::future::join_all(calls).await {
+        result.unwrap();
+    }
+    assert_eq!(gauge.peak.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn batch_continues_or_stops_on_error() {
+    let addr = panel().await;
+    let client = token_builder(addr).max_in_flight(1).build().unwrap();
+    let emails = ["a", "missing", "c"];
+
+    let all: Vec<_> = client
+        .get_client_traffic_batch(emails, BatchMode::Continue)
+        .collect()
+        .await;
+    assert_eq!(all.len(), 3);
+    assert_eq!(all.iter().filter(|(_, r)| r.is_err()).count(), 1);
+
+    let stopped: Vec<_> = client
+        .get_client_traffic_batch(emails, BatchMode::StopOnError)
+        .collect()
+        .await;
+    let keys: Vec<_> = stopped.iter().map(|(k, _)| k.as_str()).collect();
+    assert_eq!(keys, ["a", "missing"]);
+    assert!(stopped[1].1.is_err());
+}
+
+#[tokio::test]
+async fn ips_batch_yields_per_key_results() {
+    let addr = serve(|_| ok(json!("No IP Record"))).await;
+    let client = token_builder(addr).build().unwrap();
+
+    let mut results: Vec<_> = client
+        .get_client_ips_batch(vec!["a".to_string(), "b".to_string()], BatchMode::Continue)
+        .collect()
+        .await;
+    results.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(results[0].0, "a");
+    assert!(results.iter().all(|(_, r)| r.is_ok()));
+}
@@ -0,0 +1,57 @@
+#![cfg(feature = "blocking")]
+
+mod common;
+
+use common::{fail, ok, serve};
+use rustix3::blocking::Client;
+use rustix3::error::Error;
+use rustix3::{AuthToken, BatchMode, ClientOptions};
+use serde_json::json;
+use std::net::SocketAddr;
+use tokio::runtime::Runtime;
+
+fn panel(server: &Runtime) -> SocketAddr {
+    server.block_on(serve(|req| match req.path.as_str() {
+        "/login" => ok(json!(null)),
+        "/panel/api/inbounds/list" => ok(json!([])),
+        "/panel/api/inbounds/getClientTraffics/missing" => fail("client not found"),
+        path if path.starts_with("/panel/api/inbounds/getClientTraffics/") => ok(json!({
+            "id": 1, "inboundId": 1, "enable": true, "email": "a", "up": 5, "down": 6,
+            "expiryTime": 0, "total": 0, "reset": 0
+        })),
+        "/panel/api/server/getNewUUID" => {
+            ok(json!({ "uuid": "7c4b6bb1-8a6e-4a53-9f30-0f3cfd1c1f4e" }))
+        }
+        _ => fail("unexpected"),
+    }))
+}
+
+#[test]
+fn blocking_client_logs_in_and_calls_api() {
+    let server = Runtime::new().unwrap();
+    let addr = panel(&server);
+
+    let client = Client::new("admin", "admin", format!("http://{addr}/")).unwrap();
+    assert!(client.get_inbounds_list().unwrap().is_empty());
+    assert_eq!(client.get_client_traffic_by_email("a").unwrap().up, 5);
+    client.get_new_uuid().unwrap();
+
+    let err = client.delete_depleted_clients(9).unwrap_err();
+    assert!(matches!(err, Error::ApiError { ref message, .. } if message == "unexpected"));
+}
+
+#[test]
+fn blocking_client_from_token_runs_batches() {
+    let server = Runtime::new().unwrap();
+    let addr = panel(&server);
+
+    let client = Client::from_token(
+        AuthToken::new("tok"),
+        format!("http://{addr}/"),
+        ClientOptions::default(),
+    )
+    .unwrap();
+    let results = client.get_client_traffic_batch(["a", "b", "missing"], BatchMode::Continue);
+    assert_eq!(results.len(), 3);
+    assert_eq!(results.iter().filter(|(_, r)| r.is_ok()).count(), 2);
+}
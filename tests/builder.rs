@@ -0,0 +1,69 @@
+mod common;
+
+use common::{ok, serve};
+use rustix3::Client;
+use rustix3::error::Error;
+use serde_json::json;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+async fn counting_panel() -> (String, Arc<AtomicUsize>) {
+    let logins = Arc::new(AtomicUsize::new(0));
+    let counter = logins.clone();
+    let addr = serve(move |req| {
+        if req.path == "/login" {
+            counter.fetch_add(1, Ordering::SeqCst);
+            return ok(json!({ "username": "admin" })).with_header("Set-Cookie", "3x-ui=s; Path=/");
+        }
+        ok(json!([]))
+    })
+    .await;
+    (format!("http://{addr}/"), logins)
+}
+
+#[tokio::test]
+async fn build_does_not_touch_the_network() {
+    let client = Client::builder("http://127.0.0.1:1/")
+        .credentials("admin", "admin")
+        .retry_count(0)
+        .connect_timeout(Duration::from_millis(200))
+        .build()
+        .unwrap();
+    let err = client.get_inbounds_list().await.unwrap_err();
+    assert!(matches!(err, Error::Connection(_)), "{err:?}");
+}
+
+#[tokio::test]
+async fn first_call_logs_in_once() {
+    let (url, logins) = counting_panel().await;
+    let client = Client::builder(url)
+        .credentials("admin", "admin")
+        .build()
+        .unwrap();
+    assert_eq!(logins.load(Ordering::SeqCst), 0);
+
+    let results = futures::future::join_all((0..4).map(|_| client.get_inbounds_list())).await;
+    assert!(results.iter().all(|r| r.is_ok()));
+    assert_eq!(logins.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn explicit_login_returns_result() {
+    let (url, logins) = counting_panel().await;
+    let client = Client::builder(url)
+        .credentials("admin", "admin")
+        .build()
+        .unwrap();
+
+    let result = client.login().await.unwrap();
+    assert_eq!(result.details.unwrap().username.as_deref(), Some("admin"));
+    client.get_inbounds_list().await.unwrap();
+    assert_eq!(logins.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn invalid_url_is_reported_on_build() {
+    let err = Client::builder("not a url").build().unwrap_err();
+    assert!(matches!(err, Error::InvalidUrl(_)), "{err:?}");
+}
@@ -0,0 +1,205 @@
+mod common;
+
+use common::{MockResponse, ok, serve, token_builder};
+use reqwest::header::HeaderValue;
+use rustix3::error::Error;
+use rustix3::retry::retry_after;
+use rustix3::{CallOptions, CancellationToken, Client};
+use serde_json::json;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::SystemTime;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::time::{Duration, Instant, sleep};
+
+/// A panel that accepts connections but never answers.
+async fn silent_panel() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let mut held = Vec::new();
+        while let Ok((stream, _)) = listener.accept().await {
+            held.push(stream);
+        }
+    });
+    addr
+}
+
+/// A panel that sends the response headers but stalls before the body.
+async fn stalled_body_panel() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let mut held = Vec::new();
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let mut buf = [0; 4096];
+            let _ = stream.read(&mut buf).await;
+            let _ = stream
+                .write_all(
+                    b"HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: 100\r\n\r\n{",
+                )
+                .await;
+            held.push(stream);
+        }
+    });
+    addr
+}
+
+async fn overloaded_panel(hits: Arc<AtomicUsize>) -> SocketAddr {
+    serve(move |_| {
+        hits.fetch_add(1, Ordering::SeqCst);
+        MockResponse::status(503)
+    })
+    .await
+}
+
+fn client(addr: SocketAddr, retry_count: u32) -> Client {
+    token_builder(addr)
+        .retry_count(retry_count)
+        .retry_base_delay(Duration::from_millis(10))
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn per_call_timeout_overrides_request_timeout() {
+    let client = client(silent_panel().await, 0);
+    let started = Instant::now();
+    let err = CallOptions::new()
+        .timeout(Duration::from_millis(100))
+        .scope(client.get_inbounds_list())
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::Connection(ref e) if e.is_timeout()));
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+#[tokio::test]
+async fn retry_count_override_applies_in_both_directions() {
+    let hits = Arc::new(AtomicUsize::new(0));
+    let client = client(overloaded_panel(hits.clone()).await, 0);
+    CallOptions::new()
+        .retry_count(2)
+        .scope(client.get_inbounds_list())
+        .await
+        .unwrap_err();
+    assert_eq!(hits.swap(0, Ordering::SeqCst), 3);
+
+    let client = token_builder(overloaded_panel(hits.clone()).await)
+        .retry_count(3)
+        .build()
+        .unwrap();
+    CallOptions::new()
+        .retry_count(0)
+        .scope(client.get_inbounds_list())
+        .await
+        .unwrap_err();
+    assert_eq!(hits.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn deadline_budget_is_shared_across_retries() {
+    let hits = Arc::new(AtomicUsize::new(0));
+    let client = token_builder(overloaded_panel(hits.clone()).await)
+        .retry_count(10)
+        .retry_base_delay(Duration::from_millis(100))
+        .retry_max_delay(Duration::from_millis(100))
+        .build()
+        .unwrap();
+
+    let started = Instant::now();
+    CallOptions::new()
+        .budget(Duration::from_millis(250))
+        .scope(client.get_inbounds_list())
+        .await
+        .unwrap_err();
+    assert!(started.elapsed() < Duration::from_millis(400));
+    assert!(hits.load(Ordering::SeqCst) <= 3);
+}
+
+#[tokio::test]
+async fn expired_deadline_fails_with_deadline_exceeded() {
+    let client = client(silent_panel().await, 0);
+    let err = CallOptions::new()
+        .budget(Duration::from_millis(50))
+        .scope(client.get_inbounds_list())
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::DeadlineExceeded), "{err:?}");
+}
+
+#[tokio::test]
+async fn cancellation_token_aborts_call() {
+    let client = client(silent_panel().await, 0);
+    let token = CancellationToken::new();
+    let canceller = token.clone();
+    tokio::spawn(async move {
+        sleep(Duration::from_millis(50)).await;
+        canceller.cancel();
+    });
+
+    let err = CallOptions::new()
+        .cancel_on(token)
+        .scope(client.get_inbounds_list())
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::Cancelled));
+}
+
+#[tokio::test]
+async fn cancellation_token_aborts_body_read() {
+    let client = client(stalled_body_panel().await, 0);
+    let token = CancellationToken::new();
+    let canceller = token.clone();
+    tokio::spawn(async move {
+        sleep(Duration::from_millis(100)).await;
+        canceller.cancel();
+    });
+
+    let call = CallOptions::new()
+        .cancel_on(token)
+        .scope(client.get_inbounds_list());
+    let err = tokio::time::timeout(Duration::from_secs(2), call)
+        .await
+        .expect("body read was not cancelled")
+        .unwrap_err();
+    assert!(matches!(err, Error::Cancelled));
+}
+
+#[tokio::test]
+async fn retry_after_http_date_is_honored() {
+    let hits = Arc::new(AtomicUsize::new(0));
+    let server_hits = hits.clone();
+    let addr = serve(move |_| {
+        if server_hits.fetch_add(1, Ordering::SeqCst) == 0 {
+            let at = httpdate::fmt_http_date(SystemTime::now() + Duration::from_secs(1));
+            MockResponse::status(503).with_header("Retry-After", &at)
+        } else {
+            ok(json!([]))
+        }
+    })
+    .await;
+
+    let started = Instant::now();
+    client(addr, 1).get_inbounds_list().await.unwrap();
+    assert_eq!(hits.load(Ordering::SeqCst), 2);
+    assert!(started.elapsed() <= Duration::from_secs(2));
+}
+
+#[test]
+fn retry_after_parses_seconds_and_dates() {
+    let secs = HeaderValue::from_static("3");
+    assert_eq!(retry_after(Some(&secs)), Some(Duration::from_secs(3)));
+
+    let future = httpdate::fmt_http_date(SystemTime::now() + Duration::from_secs(30));
+    let delay = retry_after(Some(&HeaderValue::from_str(&future).unwrap())).unwrap();
+    assert!(delay > Duration::from_secs(28) && delay <= Duration::from_secs(30));
+
+    let past = HeaderValue::from_static("Sun, 06 Nov 1994 08:49:37 GMT");
+    assert_eq!(retry_after(Some(&past)), Some(Duration::ZERO));
+
+    let junk = HeaderValue::from_static("soon");
+    assert_eq!(retry_after(Some(&junk)), None);
+}
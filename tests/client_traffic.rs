@@ -0,0 +1,70 @@
+mod common;
+
+use common::{Requests, ok, serve_recording, token_client};
+use rustix3::error::Error;
+use serde_json::{Value, json};
+use std::net::SocketAddr;
+
+async fn panel() -> (SocketAddr, Requests) {
+    serve_recording(|req| match req.path.as_str() {
+        "/panel/api/inbounds/getClientTraffics/alice" => ok(json!({
+            "id": 1, "inboundId": 1, "enable": true, "email": "alice", "up": 1, "down": 2,
+            "expiryTime": 0, "total": 0, "reset": 0
+        })),
+        "/panel/api/inbounds/getClientTraffics/ghost" => ok(json!(null)),
+        _ => ok(json!(null)),
+    })
+    .await
+}
+
+#[tokio::test]
+async fn update_client_traffic_sets_counters() {
+    let (addr, requests) = panel().await;
+    let client = token_client(addr);
+
+    client
+        .update_client_traffic("alice", 5_000_000_000, 70_000_000_000)
+        .await
+        .unwrap();
+
+    let requests = requests.lock().unwrap();
+    let update = requests.last().unwrap();
+    assert_eq!(update.path, "/panel/api/inbounds/updateClientTraffic/alice");
+    let body: Value = serde_json::from_str(&update.body_str()).unwrap();
+    assert_eq!(
+        body,
+        json!({ "upload": 5_000_000_000_i64, "download": 70_000_000_000_i64 })
+    );
+}
+
+#[tokio::test]
+async fn update_client_traffic_rejects_unknown_email_and_overflow() {
+    let (addr, requests) = panel().await;
+    let client = token_client(addr);
+
+    let err = client
+        .update_client_traffic("ghost", 1, 1)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        Error::ClientNotFound {
+            endpoint: "update_client_traffic",
+            ..
+        }
+    ));
+
+    let err = client
+        .update_client_traffic("alice", 1, u128::from(u64::MAX))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::CounterOverflow { field: "down", .. }));
+    assert_eq!(err.endpoint(), None);
+
+    let requests = requests.lock().unwrap();
+    assert!(
+        requests
+            .iter()
+            .all(|r| !r.path.contains("updateClientTraffic"))
+    );
+}
@@ -0,0 +1,214 @@
+#![allow(dead_code)]
+
+use rustix3::{AuthToken, Client, ClientBuilder};
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+#[derive(Debug, Clone)]
+pub struct MockRequest {
+    pub method: String,
+    pub path: String,
+    pub headers: BTreeMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+impl MockRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .get(&name.to_ascii_lowercase())
+            .map(String::as_str)
+    }
+
+    pub fn body_str(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MockResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl MockResponse {
+    pub fn json(body: serde_json::Value) -> Self {
+        Self {
+            status: 200,
+            headers: vec![("Content-Type".into(), "application/json".into())],
+            body: body.to_string().into_bytes(),
+        }
+    }
+
+    pub fn status(status: u16) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+}
+
+pub fn ok(obj: serde_json::Value) -> MockResponse {
+    MockResponse::json(serde_json::json!({ "success": true, "msg": "", "obj": obj }))
+}
+
+pub fn fail(msg: &str) -> MockResponse {
+    MockResponse::json(serde_json::json!({ "success": false, "msg": msg, "obj": null }))
+}
+
+pub type Handler = Arc<dyn Fn(MockRequest) -> MockResponse + Send + Sync>;
+
+/// Start a plain HTTP mock panel on a random local port.
+pub async fn serve(
+    handler: impl Fn(MockRequest) -> MockResponse + Send + Sync + 'static,
+) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let handler: Handler = Arc::new(handler);
+    tokio::spawn(async move {
+        loop {
+            let Ok((stream, _)) = listener.accept().await else {
+                break;
+            };
+            let handler = handler.clone();
+            tokio::spawn(async move {
+                let _ = handle_connection(stream, handler).await;
+            });
+        }
+    });
+    addr
+}
+
+/// Requests seen by a [`serve_recording`] panel, in arrival order.
+pub type Requests = Arc<Mutex<Vec<MockRequest>>>;
+
+/// Start a plain HTTP mock panel that records every request before answering it.
+pub async fn serve_recording(
+    handler: impl Fn(&MockRequest) -> MockResponse + Send + Sync + 'static,
+) -> (SocketAddr, Requests) {
+    let requests = Requests::default();
+    let recorded = requests.clone();
+    let addr = serve(move |req| {
+        recorded.lock().unwrap().push(req.clone());
+        handler(&req)
+    })
+    .await;
+    (addr, requests)
+}
+
+/// Builder for a client that authenticates with a fixed bearer token, so no login is made.
+pub fn token_builder(addr: SocketAddr) -> ClientBuilder {
+    Client::builder(format!("http://{addr}/")).token(AuthToken::new("tok"))
+}
+
+pub fn token_client(addr: SocketAddr) -> Client {
+    token_builder(addr).build().unwrap()
+}
+
+/// Start an HTTPS mock panel with the given server TLS config.
+pub async fn serve_tls(
+    config: Arc<rustls::ServerConfig>,
+    handler: impl Fn(MockRequest) -> MockResponse + Send + Sync + 'static,
+) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let acceptor = tokio_rustls::TlsAcceptor::from(config);
+    let handler: Handler = Arc::new(handler);
+    tokio::spawn(async move {
+        loop {
+            let Ok((stream, _)) = listener.accept().await else {
+                break;
+            };
+            let acceptor = acceptor.clone();
+            let handler = handler.clone();
+            tokio::spawn(async move {
+                if let Ok(stream) = acceptor.accept(stream).await {
+                    let _ = handle_connection(stream, handler).await;
+                }
+            });
+        }
+    });
+    addr
+}
+
+pub async fn handle_connection<S>(mut stream: S, handler: Handler) -> std::io::Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let Some(request) = read_request(&mut stream).await? else {
+        return Ok(());
+    };
+    let response = handler(request);
+    write_response(&mut stream, &response).await
+}
+
+pub async fn read_request<S>(stream: &mut S) -> std::io::Result<Option<MockRequest>>
+where
+    S: AsyncRead + Unpin,
+{
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let head_end = loop {
+        if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos;
+        }
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok(None);
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    };
+    let head = String::from_utf8_lossy(&buf[..head_end]).into_owned();
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next().unwrap_or_default().split(' ');
+    let method = request_line.next().unwrap_or_default().to_string();
+    let path = request_line.next().unwrap_or_default().to_string();
+    let headers: BTreeMap<String, String> = lines
+        .filter_map(|l| l.split_once(':'))
+        .map(|(k, v)| (k.trim().to_ascii_lowercase(), v.trim().to_string()))
+        .collect();
+    let len: usize = headers
+        .get("content-length")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+    let mut body = buf[head_end + 4..].to_vec();
+    while body.len() < len {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            break;
+        }
+        body.extend_from_slice(&chunk[..n]);
+    }
+    Ok(Some(MockRequest {
+        method,
+        path,
+        headers,
+        body,
+    }))
+}
+
+pub async fn write_response<S>(stream: &mut S, response: &MockResponse) -> std::io::Result<()>
+where
+    S: AsyncWrite + Unpin,
+{
+    let mut out = format!("HTTP/1.1 {} MOCK\r\n", response.status);
+    for (k, v) in &response.headers {
+        out.push_str(&format!("{k}: {v}\r\n"));
+    }
+    out.push_str(&format!(
+        "Content-Length: {}\r\nConnection: close\r\n\r\n",
+        response.body.len()
+    ));
+    stream.write_all(out.as_bytes()).await?;
+    stream.write_all(&response.body).await?;
+    stream.shutdown().await
+}
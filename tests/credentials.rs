@@ -0,0 +1,102 @@
+mod common;
+
+use common::{MockResponse, fail, ok, serve};
+use rustix3::{
+    Client, CredentialProvider, Credentials, EnvCredentials, FileCredentials, FnCredentials,
+    SecretString,
+};
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+async fn panel(password: &'static str) -> String {
+    let addr = serve(move |req| {
+        let body: Value = serde_json::from_slice(&req.body).unwrap_or_default();
+        match req.path.as_str() {
+            "/login" if body["password"] == password => {
+                ok(json!(null)).with_header("Set-Cookie", "3x-ui=session-cookie-value; Path=/")
+            }
+            "/login" => fail("wrong username or password"),
+            _ => ok(json!([])),
+        }
+    })
+    .await;
+    format!("http://{addr}/")
+}
+
+#[test]
+fn secret_string_is_redacted() {
+    let secret = SecretString::from("hunter2");
+    assert_eq!(format!("{secret}"), "<redacted>");
+    assert_eq!(format!("{secret:?}"), "<redacted>");
+    assert_eq!(secret.expose_secret(), "hunter2");
+}
+
+#[tokio::test]
+async fn client_debug_does_not_leak_password() {
+    let url = panel("hunter2").await;
+    let client = Client::new("admin", "hunter2", url).await.unwrap();
+    let debug = format!("{client:?}");
+    assert!(!debug.contains("hunter2"), "{debug}");
+    assert!(!debug.contains("session-cookie-value"), "{debug}");
+    assert!(debug.contains("admin"));
+}
+
+#[tokio::test]
+async fn env_credentials_are_read_on_login() {
+    // SAFETY: the variable names are unique to this test.
+    unsafe {
+        std::env::set_var("RUSTIX3_TEST_ENV_USER", "admin");
+        std::env::set_var("RUSTIX3_TEST_ENV_PASS", "from-env");
+    }
+    let provider = EnvCredentials::new("RUSTIX3_TEST_ENV_USER", "RUSTIX3_TEST_ENV_PASS");
+    let url = panel("from-env").await;
+    let client = Client::builder(url)
+        .credential_provider(Arc::new(provider))
+        .build()
+        .unwrap();
+    client.login().await.unwrap();
+}
+
+#[tokio::test]
+async fn file_credentials_strip_trailing_newline() {
+    let path = std::env::temp_dir().join(format!("rustix3-pass-{}", uuid::Uuid::new_v4()));
+    std::fs::write(&path, "from-file\n").unwrap();
+    let provider = FileCredentials::new("admin", &path);
+    let creds = provider.credentials().await.unwrap();
+    assert_eq!(creds.password.expose_secret(), "from-file");
+
+    let url = panel("from-file").await;
+    let client = Client::builder(url)
+        .credential_provider(Arc::new(provider))
+        .build()
+        .unwrap();
+    client.login().await.unwrap();
+    let _ = std::fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn async_closure_credentials() {
+    let provider = FnCredentials::new(|| async { Ok(Credentials::new("admin", "from-vault")) });
+    let url = panel("from-vault").await;
+    let client = Client::builder(url)
+        .credential_provider(Arc::new(provider))
+        .build()
+        .unwrap();
+    client.login().await.unwrap();
+}
+
+#[tokio::test]
+async fn form_login_fallback_sends_password() {
+    let addr = serve(|req| match req.path.as_str() {
+        "/login" => MockResponse::status(404),
+        "/login/" if req.body_str() == "username=admin&password=hunter2&twoFactorCode=" => {
+            ok(json!(null))
+        }
+        "/login/" => fail("wrong username or password"),
+        _ => ok(json!([])),
+    })
+    .await;
+    Client::new("admin", "hunter2", format!("http://{addr}/"))
+        .await
+        .unwrap();
+}
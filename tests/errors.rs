@@ -0,0 +1,130 @@
+mod common;
+
+use common::{MockResponse, fail, ok, serve, token_builder};
+use reqwest::StatusCode;
+use rustix3::Client;
+use rustix3::error::Error;
+use rustix3::models::{ClientRequest, ClientSettings};
+use serde_json::json;
+use std::net::SocketAddr;
+
+async fn panel() -> SocketAddr {
+    serve(|req| match req.path.as_str() {
+        "/panel/api/inbounds/get/1" => fail("record not found"),
+        "/panel/api/inbounds/addClient" => fail("record not found"),
+        "/panel/api/inbounds/clearClientIps/gone" => fail("record not found"),
+        "/panel/api/inbounds/del/2" => fail("Port already exists: 443"),
+        "/panel/api/inbounds/clearClientIps/a" => fail("Duplicate email: a"),
+        "/panel/api/inbounds/getClientTraffics/missing" => ok(json!(null)),
+        "/panel/api/inbounds/createbackup" => MockResponse::status(500),
+        "/panel/api/inbounds/list" => MockResponse::status(503),
+        _ => fail("something else"),
+    })
+    .await
+}
+
+fn client(addr: SocketAddr) -> Client {
+    token_builder(addr).retry_count(0).build().unwrap()
+}
+
+#[tokio::test]
+async fn panel_messages_are_classified_per_endpoint() {
+    let client = client(panel().await);
+
+    let err = client.get_inbound_by_id(1).await.unwrap_err();
+    assert!(matches!(
+        err,
+        Error::InboundNotFound {
+            endpoint: "get_inbound_by_id",
+            ..
+        }
+    ));
+    let err = client.delete_inbound(2).await.unwrap_err();
+    assert!(matches!(err, Error::PortInUse { .. }));
+    assert_eq!(err.endpoint(), Some("delete_inbound"));
+    let err = client.clear_client_ips("a").await.unwrap_err();
+    assert!(matches!(err, Error::DuplicateEmail { .. }));
+
+    let err = client.delete_depleted_clients(3).await.unwrap_err();
+    assert!(matches!(
+        err,
+        Error::ApiError { endpoint: "delete_depleted_clients", status, ref message }
+            if status == StatusCode::OK && message == "something else"
+    ));
+    assert!(!err.is_retryable());
+}
+
+#[tokio::test]
+async fn record_not_found_names_the_endpoint_lookup() {
+    let client = client(panel().await);
+
+    let request = ClientRequest {
+        id: 9,
+        settings: ClientSettings { clients: vec![] },
+    };
+    let err = client.add_client_to_inbound(&request).await.unwrap_err();
+    assert!(matches!(
+        err,
+        Error::InboundNotFound {
+            endpoint: "add_client_to_inbound",
+            ..
+        }
+    ));
+    let err = client.clear_client_ips("gone").await.unwrap_err();
+    assert!(matches!(
+        err,
+        Error::ClientNotFound {
+            endpoint: "clear_client_ips",
+            ..
+        }
+    ));
+}
+
+#[tokio::test]
+async fn missing_client_traffic_is_client_not_found() {
+    let client = client(panel().await);
+
+    let err = client
+        .get_client_traffic_by_email("missing")
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        Error::ClientNotFound {
+            endpoint: "get_client_traffic_by_email",
+            ..
+        }
+    ));
+}
+
+#[tokio::test]
+async fn http_failures_keep_endpoint_and_status() {
+    let client = client(panel().await);
+
+    let err = client.send_backup_by_bot().await.unwrap_err();
+    assert!(matches!(
+        err,
+        Error::HttpStatus {
+            endpoint: "send_backup_by_bot",
+            ..
+        }
+    ));
+    assert_eq!(err.status(), Some(StatusCode::INTERNAL_SERVER_ERROR));
+    assert!(err.is_retryable());
+
+    let err = client.get_inbounds_list().await.unwrap_err();
+    assert_eq!(err.endpoint(), Some("get_inbounds_list"));
+    assert_eq!(err.status(), Some(StatusCode::SERVICE_UNAVAILABLE));
+}
+
+#[test]
+fn auth_messages_are_classified() {
+    let err = Error::from_panel_message(
+        "online_clients",
+        StatusCode::UNAUTHORIZED,
+        "unauthorized".into(),
+    );
+    assert!(matches!(err, Error::AuthExpired { .. }));
+    let err = Error::from_panel_message("logs", StatusCode::OK, "Client not found".into());
+    assert!(matches!(err, Error::ClientNotFound { .. }));
+}
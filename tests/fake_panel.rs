@@ -0,0 +1,42 @@
+#![cfg(feature = "test-util")]
+
+use rustix3::models::Inbounds;
+use rustix3::{FakePanel, PanelApi};
+use serde_json::json;
+use std::sync::Arc;
+
+fn inbound(id: u64, remark: &str, enable: bool) -> Inbounds {
+    serde_json::from_value(json!({
+        "id": id, "up": 0, "down": 0, "total": 0, "remark": remark, "enable": enable,
+        "expiryTime": 0, "port": 30000 + id, "protocol": "vless", "tag": format!("inbound-{id}"),
+        "settings": "{\"clients\":[]}", "streamSettings": null
+    }))
+    .unwrap()
+}
+
+#[tokio::test]
+async fn fake_answers_configured_methods_through_trait_object() {
+    let fake = Arc::new(FakePanel::new().on("get_inbounds_list", || {
+        Ok(vec![inbound(1, "on", true), inbound(2, "off", false)])
+    }));
+    let api: Arc<dyn PanelApi> = fake.clone();
+
+    let inbounds = api.get_inbounds_list().await.unwrap();
+    assert_eq!(inbounds.len(), 2);
+    assert_eq!(inbounds[0].remark, "on");
+
+    let err = api.get_inbound_by_id(1).await.unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("no response for get_inbound_by_id"),
+        "{err}"
+    );
+    assert_eq!(fake.calls(), ["get_inbounds_list", "get_inbound_by_id"]);
+}
+
+#[tokio::test]
+async fn mistyped_response_is_reported() {
+    let fake = FakePanel::new().on("get_inbounds_list", || Ok("not a list"));
+    let err = fake.get_inbounds_list().await.unwrap_err();
+    assert!(err.to_string().contains("wrong type"), "{err}");
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use common::{ok, serve, token_client};
+use serde_json::json;
+use std::net::SocketAddr;
+use std::time::{Duration, UNIX_EPOCH};
+
+fn stats(email: &str, last_online: i64) -> serde_json::Value {
+    json!({
+        "id": 1, "inboundId": 1, "enable": true, "email": email, "up": 0, "down": 0,
+        "expiryTime": 0, "total": 0, "reset": 0, "lastOnline": last_online
+    })
+}
+
+async fn panel() -> SocketAddr {
+    serve(|req| match req.path.as_str() {
+        "/panel/api/inbounds/lastOnline" => ok(json!({
+            "alice": 1_700_000_000_123_i64,
+            "never": 0
+        })),
+        "/panel/api/inbounds/list" => ok(json!([{
+            "id": 1, "up": 0, "down": 0, "total": 0, "remark": "r", "enable": true,
+            "expiryTime": 0, "port": 443, "protocol": "vless", "tag": "inbound-443",
+            "settings": { "clients": [] }, "streamSettings": null,
+            "clientStats": [
+                stats("alice", 1_600_000_000_000),
+                stats("bob", 1_650_000_000_000),
+                stats("carol", 0)
+            ]
+        }])),
+        _ => ok(json!(null)),
+    })
+    .await
+}
+
+#[tokio::test]
+async fn last_online_maps_email_to_time() {
+    let client = token_client(panel().await);
+
+    let last_online = client.get_last_online().await.unwrap();
+    assert_eq!(last_online.len(), 1);
+    assert_eq!(
+        last_online["alice"],
+        UNIX_EPOCH + Duration::from_millis(1_700_000_000_123)
+    );
+}
+
+#[tokio::test]
+async fn merged_fills_gaps_from_client_stats() {
+    let client = token_client(panel().await);
+
+    let last_online = client.get_last_online_merged().await.unwrap();
+    assert_eq!(
+        last_online["alice"],
+        UNIX_EPOCH + Duration::from_millis(1_700_000_000_123)
+    );
+    assert_eq!(
+        last_online["bob"],
+        UNIX_EPOCH + Duration::from_millis(1_650_000_000_000)
+    );
+    assert!(!last_online.contains_key("carol"));
+    assert!(!last_online.contains_key("never"));
+}
@@ -0,0 +1,126 @@
+mod common;
+
+use common::{MockResponse, ok, serve, token_builder};
+use rustix3::error::Error;
+use rustix3::{CircuitBreaker, Client};
+use serde_json::json;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use tokio::time::{Duration, Instant, sleep};
+
+fn breaker() -> CircuitBreaker {
+    CircuitBreaker {
+        failure_threshold: 2,
+        cool_down: Duration::from_millis(200),
+        half_open_probes: 1,
+    }
+}
+
+async fn flaky_panel(healthy: Arc<AtomicBool>, hits: Arc<AtomicUsize>) -> SocketAddr {
+    serve(move |_| {
+        hits.fetch_add(1, Ordering::SeqCst);
+        if healthy.load(Ordering::SeqCst) {
+            ok(json!([]))
+        } else {
+            MockResponse::status(500)
+        }
+    })
+    .await
+}
+
+fn client(addr: SocketAddr) -> Client {
+    token_builder(addr)
+        .retry_count(0)
+        .circuit_breaker(breaker())
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn rate_limit_spaces_requests_after_burst() {
+    let addr = serve(|_| ok(json!([]))).await;
+    let client = token_builder(addr).rate_limit(20.0, 2).build().unwrap();
+
+    let started = Instant::now();
+    for _ in 0..5 {
+        client.get_inbounds_list().await.unwrap();
+    }
+    assert!(started.elapsed() >= Duration::from_millis(140));
+}
+
+#[tokio::test]
+async fn open_breaker_fails_fast_and_recovers_after_cool_down() {
+    let healthy = Arc::new(AtomicBool::new(false));
+    let hits = Arc::new(AtomicUsize::new(0));
+    let client = client(flaky_panel(healthy.clone(), hits.clone()).await);
+
+    for _ in 0..2 {
+        let err = client.get_inbounds_list().await.unwrap_err();
+        assert!(!matches!(err, Error::CircuitOpen { .. }));
+    }
+    let err = client.get_inbounds_list().await.unwrap_err();
+    assert!(
+        matches!(err, Error::CircuitOpen { retry_after } if retry_after <= Duration::from_millis(200))
+    );
+    assert_eq!(hits.load(Ordering::SeqCst), 2);
+
+    sleep(Duration::from_millis(250)).await;
+    healthy.store(true, Ordering::SeqCst);
+    client.get_inbounds_list().await.unwrap();
+    client.get_inbounds_list().await.unwrap();
+    assert_eq!(hits.load(Ordering::SeqCst), 4);
+}
+
+#[tokio::test]
+async fn failed_probe_reopens_breaker() {
+    let healthy = Arc::new(AtomicBool::new(false));
+    let hits = Arc::new(AtomicUsize::new(0));
+    let client = client(flaky_panel(healthy, hits.clone()).await);
+
+    for _ in 0..2 {
+        client.get_inbounds_list().await.unwrap_err();
+    }
+    sleep(Duration::from_millis(250)).await;
+    let err = client.get_inbounds_list().await.unwrap_err();
+    assert!(!matches!(err, Error::CircuitOpen { .. }));
+    assert_eq!(hits.load(Ordering::SeqCst), 3);
+
+    let err = client.get_inbounds_list().await.unwrap_err();
+    assert!(matches!(err, Error::CircuitOpen { .. }));
+    assert_eq!(hits.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn dropped_probe_frees_half_open_slot() {
+    let healthy = Arc::new(AtomicBool::new(false));
+    let slow = Arc::new(AtomicBool::new(false));
+    let addr = {
+        let (healthy, slow) = (healthy.clone(), slow.clone());
+        serve(move |_| {
+            if slow.load(Ordering::SeqCst) {
+                std::thread::sleep(Duration::from_millis(300));
+            }
+            if healthy.load(Ordering::SeqCst) {
+                ok(json!([]))
+            } else {
+                MockResponse::status(500)
+            }
+        })
+        .await
+    };
+    let client = client(addr);
+
+    for _ in 0..2 {
+        client.get_inbounds_list().await.unwrap_err();
+    }
+    sleep(Duration::from_millis(250)).await;
+
+    slow.store(true, Ordering::SeqCst);
+    let probe = tokio::time::timeout(Duration::from_millis(50), client.get_inbounds_list()).await;
+    assert!(probe.is_err());
+
+    slow.store(false, Ordering::SeqCst);
+    healthy.store(true, Ordering::SeqCst);
+    client.get_inbounds_list().await.unwrap();
+}
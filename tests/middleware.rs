@@ -0,0 +1,151 @@
+mod common;
+
+use common::{MockResponse, ok, serve, token_builder};
+use futures::future::BoxFuture;
+use reqwest::header::HeaderValue;
+use reqwest::{Request, Response};
+use rustix3::error::Error;
+use rustix3::{Client, Layer, Middleware, Next};
+use serde_json::json;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::time::Duration;
+
+#[derive(Debug, Default)]
+struct Counter(AtomicUsize);
+
+impl Middleware for Counter {
+    fn handle<'a>(
+        &'a self,
+        request: Request,
+        next: Next<'a>,
+    ) -> BoxFuture<'a, rustix3::Result<Response>> {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        next.run(request)
+    }
+}
+
+#[derive(Debug)]
+struct Tag;
+
+impl Middleware for Tag {
+    fn handle<'a>(
+        &'a self,
+        mut request: Request,
+        next: Next<'a>,
+    ) -> BoxFuture<'a, rustix3::Result<Response>> {
+        request
+            .headers_mut()
+            .insert("x-audit", HeaderValue::from_static("job-42"));
+        next.run(request)
+    }
+}
+
+#[derive(Debug)]
+struct Offline;
+
+impl Middleware for Offline {
+    fn handle<'a>(
+        &'a self,
+        _request: Request,
+        _next: Next<'a>,
+    ) -> BoxFuture<'a, rustix3::Result<Response>> {
+        Box::pin(async { Err(Error::OtherError("injected fault".into())) })
+    }
+}
+
+#[tokio::test]
+async fn custom_layers_run_between_built_in_layers() {
+    let hits = Arc::new(AtomicUsize::new(0));
+    let server_hits = hits.clone();
+    let addr = serve(move |req| {
+        assert_eq!(req.header("x-audit"), Some("job-42"));
+        assert_eq!(req.header("authorization"), Some("Bearer tok"));
+        if server_hits.fetch_add(1, Ordering::SeqCst) < 2 {
+            MockResponse::status(503)
+        } else {
+            ok(json!([]))
+        }
+    })
+    .await;
+
+    let outer = Arc::new(Counter::default());
+    let inner = Arc::new(Counter::default());
+    let client = token_builder(addr)
+        .retry_base_delay(Duration::from_millis(1))
+        .layers(vec![
+            Layer::Auth,
+            Layer::Custom(outer.clone()),
+            Layer::Retry,
+            Layer::Custom(inner.clone()),
+            Layer::Timeout,
+        ])
+        .layer(Tag)
+        .build()
+        .unwrap();
+
+    assert!(client.get_inbounds_list().await.unwrap().is_empty());
+    assert_eq!(outer.0.load(Ordering::SeqCst), 1);
+    assert_eq!(inner.0.load(Ordering::SeqCst), 3);
+    assert_eq!(hits.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn middleware_can_short_circuit_requests() {
+    let hits = Arc::new(AtomicUsize::new(0));
+    let server_hits = hits.clone();
+    let addr = serve(move |_| {
+        server_hits.fetch_add(1, Ordering::SeqCst);
+        ok(json!([]))
+    })
+    .await;
+
+    let client = token_builder(addr).layer(Offline).build().unwrap();
+
+    let err = client.get_inbounds_list().await.unwrap_err();
+    assert!(matches!(err, Error::OtherError(ref m) if m == "injected fault"));
+    assert_eq!(hits.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn login_passes_through_the_stack() {
+    let addr = serve(|req| match req.path.as_str() {
+        "/login" => ok(json!(null)),
+        _ => ok(json!([])),
+    })
+    .await;
+
+    let counter = Arc::new(Counter::default());
+    let client = Client::builder(format!("http://{addr}/"))
+        .credentials("admin", "admin")
+        .layers(vec![Layer::Custom(counter.clone())])
+        .connect()
+        .await
+        .unwrap();
+    client.get_inbounds_list().await.unwrap();
+    assert_eq!(counter.0.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn request_timeout_applies_without_timeout_layer() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let mut held = Vec::new();
+        while let Ok((stream, _)) = listener.accept().await {
+            held.push(stream);
+        }
+    });
+
+    let client = token_builder(addr)
+        .layers(vec![Layer::Auth])
+        .request_timeout(Duration::from_millis(100))
+        .build()
+        .unwrap();
+    let call = tokio::time::timeout(Duration::from_secs(5), client.get_inbounds_list());
+    let err = call.await.expect("request hung").unwrap_err();
+    assert!(
+        matches!(err, Error::Connection(ref e) if e.is_timeout()),
+        "{err:?}"
+    );
+}
@@ -0,0 +1,41 @@
+mod common;
+
+use common::{Requests, ok, serve_recording, token_client};
+use serde_json::json;
+use std::net::SocketAddr;
+
+async fn panel() -> (SocketAddr, Requests) {
+    serve_recording(|req| match req.path.as_str() {
+        "/panel/xray/getOutboundsTraffic" => ok(json!([
+            { "id": 1, "tag": "direct", "up": 100, "down": 2000, "total": 2100 },
+            { "id": 2, "tag": "warp", "up": 5, "down": 7, "total": 12 }
+        ])),
+        _ => ok(json!("")),
+    })
+    .await
+}
+
+#[tokio::test]
+async fn outbound_traffic_is_keyed_by_tag() {
+    let (addr, _) = panel().await;
+    let client = token_client(addr);
+
+    let traffic = client.get_outbounds_traffic().await.unwrap();
+    assert_eq!(traffic.len(), 2);
+    assert_eq!(traffic["direct"].down, 2000);
+    assert_eq!(traffic["warp"].total, 12);
+}
+
+#[tokio::test]
+async fn reset_sends_tag() {
+    let (addr, requests) = panel().await;
+    let client = token_client(addr);
+
+    client.reset_outbound_traffic("warp").await.unwrap();
+    client.reset_all_outbounds_traffic().await.unwrap();
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests[0].path, "/panel/xray/resetOutboundsTraffic");
+    assert_eq!(requests[0].body_str(), "tag=warp");
+    assert_eq!(requests[1].body_str(), "tag=-alltags-");
+}
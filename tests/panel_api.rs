@@ -0,0 +1,43 @@
+mod common;
+
+use common::{ok, serve};
+use rustix3::{Client, PanelApi};
+use serde_json::json;
+use std::sync::Arc;
+
+async fn enabled_remarks(api: &dyn PanelApi) -> rustix3::Result<Vec<String>> {
+    Ok(api
+        .get_inbounds_list()
+        .await?
+        .into_iter()
+        .filter(|i| i.enable)
+        .map(|i| i.remark)
+        .collect())
+}
+
+fn inbound(id: u64, remark: &str, enable: bool) -> serde_json::Value {
+    json!({
+        "id": id, "up": 0, "down": 0, "total": 0, "remark": remark, "enable": enable,
+        "expiryTime": 0, "port": 30000 + id, "protocol": "vless", "tag": format!("inbound-{id}"),
+        "settings": "{\"clients\":[]}", "streamSettings": null
+    })
+}
+
+#[tokio::test]
+async fn client_is_usable_as_shared_trait_object() {
+    let addr = serve(|req| match req.path.as_str() {
+        "/login" => ok(json!(null)),
+        _ => ok(json!([inbound(1, "on", true), inbound(2, "off", false)])),
+    })
+    .await;
+
+    let client = Client::new("admin", "admin", format!("http://{addr}/"))
+        .await
+        .unwrap();
+    let api: Arc<dyn PanelApi> = Arc::new(client);
+    let task = {
+        let api = api.clone();
+        tokio::spawn(async move { enabled_remarks(api.as_ref()).await })
+    };
+    assert_eq!(task.await.unwrap().unwrap(), ["on"]);
+}
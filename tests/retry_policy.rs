@@ -0,0 +1,122 @@
+mod common;
+
+use common::{MockResponse, ok, serve, token_builder};
+use reqwest::Method;
+use rustix3::{Endpoint, ExponentialBackoff, Jitter, Outcome, RetryPolicy};
+use serde_json::json;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::time::Duration;
+
+async fn overloaded_once(hits: Arc<AtomicUsize>) -> SocketAddr {
+    serve(move |_| {
+        if hits.fetch_add(1, Ordering::SeqCst) == 0 {
+            MockResponse::status(503)
+        } else {
+            ok(json!(["alice"]))
+        }
+    })
+    .await
+}
+
+#[tokio::test]
+async fn read_only_post_is_not_retried_by_default() {
+    let hits = Arc::new(AtomicUsize::new(0));
+    let addr = overloaded_once(hits.clone()).await;
+    let client = token_builder(addr)
+        .retry_base_delay(Duration::from_millis(1))
+        .build()
+        .unwrap();
+
+    assert!(client.online_clients().await.is_err());
+    assert_eq!(hits.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn endpoint_marked_safe_is_retried() {
+    let hits = Arc::new(AtomicUsize::new(0));
+    let addr = overloaded_once(hits.clone()).await;
+    let client = token_builder(addr)
+        .retry_base_delay(Duration::from_millis(1))
+        .retry_jitter(Jitter::Full)
+        .retry_endpoint("online_clients")
+        .build()
+        .unwrap();
+
+    assert_eq!(
+        client.online_clients().await.unwrap(),
+        Some(vec!["alice".to_string()])
+    );
+    assert_eq!(hits.load(Ordering::SeqCst), 2);
+}
+
+type Decision = (String, Method, u32, Option<u16>);
+
+#[derive(Debug, Default)]
+struct Recording(Mutex<Vec<Decision>>);
+
+impl RetryPolicy for Recording {
+    fn retry(
+        &self,
+        endpoint: &Endpoint<'_>,
+        attempt: u32,
+        outcome: Outcome<'_>,
+    ) -> Option<Duration> {
+        let status = match outcome {
+            Outcome::Response(resp) => Some(resp.status().as_u16()),
+            Outcome::Error(_) => None,
+        };
+        self.0.lock().unwrap().push((
+            endpoint.name.to_string(),
+            endpoint.method.clone(),
+            attempt,
+            status,
+        ));
+        (status == Some(503)).then_some(Duration::ZERO)
+    }
+}
+
+#[tokio::test]
+async fn custom_policy_sees_endpoint_attempt_and_outcome() {
+    let hits = Arc::new(AtomicUsize::new(0));
+    let addr = overloaded_once(hits.clone()).await;
+    let policy = Arc::new(Recording::default());
+    let client = token_builder(addr)
+        .retry_policy(policy.clone())
+        .build()
+        .unwrap();
+
+    client.logs(10).await.unwrap();
+    assert_eq!(
+        *policy.0.lock().unwrap(),
+        [
+            ("logs".to_string(), Method::POST, 1, Some(503)),
+            ("logs".to_string(), Method::POST, 2, Some(200)),
+        ]
+    );
+}
+
+#[test]
+fn jitter_stays_within_bounds() {
+    let delay = Duration::from_millis(100);
+    for _ in 0..100 {
+        assert!(Jitter::Full.apply(delay) <= delay);
+        let equal = Jitter::Equal.apply(delay);
+        assert!(equal >= delay / 2 && equal <= delay);
+    }
+    assert_eq!(Jitter::None.apply(delay), delay);
+}
+
+#[test]
+fn backoff_doubles_up_to_max_delay() {
+    let policy = ExponentialBackoff {
+        base_delay: Duration::from_millis(100),
+        max_delay: Duration::from_millis(350),
+        ..ExponentialBackoff::default()
+    };
+    assert_eq!(policy.backoff(1), Duration::from_millis(100));
+    assert_eq!(policy.backoff(2), Duration::from_millis(200));
+    assert_eq!(policy.backoff(3), Duration::from_millis(350));
+    assert_eq!(policy.backoff(40), Duration::from_millis(350));
+}
@@ -0,0 +1,148 @@
+mod common;
+
+use common::{MockResponse, ok, serve};
+use rustix3::Client;
+use rustix3::error::Error;
+use rustix3::models::Inbounds;
+use serde_json::json;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+fn login_ok(session: &str) -> MockResponse {
+    ok(json!(null)).with_header("Set-Cookie", &format!("3x-ui={session}; Path=/"))
+}
+
+#[tokio::test]
+async fn relogin_and_replay_after_session_expires() {
+    let logins = Arc::new(AtomicUsize::new(0));
+    let counter = logins.clone();
+    let addr = serve(move |req| {
+        if req.path == "/login" {
+            let n = counter.fetch_add(1, Ordering::SeqCst) + 1;
+            return login_ok(&format!("s{n}"));
+        }
+        // only the second session is considered valid
+        if req.header("cookie") == Some("3x-ui=s2") {
+            ok(json!([]))
+        } else {
+            MockResponse::status(404)
+        }
+    })
+    .await;
+
+    let client = Client::new("admin", "admin", format!("http://{addr}/"))
+        .await
+        .unwrap();
+    let inbounds = client.get_inbounds_list().await.unwrap();
+    assert!(inbounds.is_empty());
+    assert_eq!(logins.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn concurrent_callers_share_one_relogin() {
+    let logins = Arc::new(AtomicUsize::new(0));
+    let counter = logins.clone();
+    let addr = serve(move |req| {
+        if req.path == "/login" {
+            let n = counter.fetch_add(1, Ordering::SeqCst) + 1;
+            return login_ok(&format!("s{n}"));
+        }
+        if req.header("cookie") == Some("3x-ui=s2") {
+            ok(json!([]))
+        } else {
+            MockResponse::status(404)
+        }
+    })
+    .await;
+
+    let client = Client::new("admin", "admin", format!("http://{addr}/"))
+        .await
+        .unwrap();
+    let results = futures::future::join_all((0..8).map(|_| client.get_inbounds_list())).await;
+    assert!(results.iter().all(|r| r.is_ok()));
+    assert_eq!(logins.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn failed_relogin_reports_session_expired() {
+    let logged_in = Arc::new(AtomicBool::new(false));
+    let flag = logged_in.clone();
+    let addr = serve(move |req| {
+        if req.path == "/login" {
+            if flag.swap(true, Ordering::SeqCst) {
+                return common::fail("wrong username or password");
+            }
+            return login_ok("s1");
+        }
+        MockResponse {
+            status: 200,
+            headers: vec![("Content-Type".into(), "text/html".into())],
+            body: b"<html>login</html>".to_vec(),
+        }
+    })
+    .await;
+
+    let client = Client::new("admin", "admin", format!("http://{addr}/"))
+        .await
+        .unwrap();
+    let err = client.server_status().await.unwrap_err();
+    assert!(matches!(err, Error::SessionExpired(_)), "{err:?}");
+}
+
+/// Panel that rejects the first session and checks the multipart body of each import.
+async fn import_panel(logins: Arc<AtomicUsize>) -> String {
+    let addr = serve(move |req| {
+        if req.path == "/login" {
+            let n = logins.fetch_add(1, Ordering::SeqCst) + 1;
+            return login_ok(&format!("s{n}"));
+        }
+        if req.header("cookie") != Some("3x-ui=s2") {
+            return MockResponse::status(404);
+        }
+        let body = req.body_str();
+        match req.path.as_str() {
+            "/panel/api/server/importDB" if body.contains("db-bytes") => ok(json!("imported")),
+            "/panel/api/inbounds/import" if body.contains("name=\"data\"") => ok(json!({
+                "id": 7, "up": 0, "down": 0, "total": 0, "remark": "r", "enable": true,
+                "expiryTime": 0, "port": 443, "protocol": "vless", "tag": "inbound-443",
+                "settings": { "clients": [] }, "streamSettings": null
+            })),
+            _ => common::fail("missing multipart body"),
+        }
+    })
+    .await;
+    format!("http://{addr}/")
+}
+
+#[tokio::test]
+async fn db_upload_is_replayed_after_relogin() {
+    let logins = Arc::new(AtomicUsize::new(0));
+    let client = Client::new("admin", "admin", import_panel(logins.clone()).await)
+        .await
+        .unwrap();
+
+    let imported = client
+        .import_db_upload("x-ui.db", b"db-bytes".to_vec())
+        .await
+        .unwrap();
+    assert_eq!(imported, "imported");
+    assert_eq!(logins.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn inbound_import_is_replayed_after_relogin() {
+    let logins = Arc::new(AtomicUsize::new(0));
+    let client = Client::new("admin", "admin", import_panel(logins.clone()).await)
+        .await
+        .unwrap();
+    let inbound: Inbounds = serde_json::from_value(json!({
+        "id": 0, "up": 0, "down": 0, "total": 0, "remark": "r", "enable": true,
+        "expiryTime": 0, "port": 443, "protocol": "vless", "tag": "inbound-443",
+        "settings": { "clients": [] }, "streamSettings": null
+    }))
+    .unwrap();
+
+    let imported = client.import_inbound(&inbound).await.unwrap();
+    assert_eq!(imported.id, 7);
+    assert_eq!(logins.load(Ordering::SeqCst), 2);
+}
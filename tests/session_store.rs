@@ -0,0 +1,139 @@
+mod common;
+
+use common::{MockResponse, ok, serve};
+use rustix3::{Client, ClientOptions, FileSessionStore, SessionStore, StoredSession};
+use serde_json::json;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+struct Panel {
+    url: String,
+    logins: Arc<AtomicUsize>,
+    valid: Arc<Mutex<Vec<String>>>,
+}
+
+async fn panel(max_age: i64) -> Panel {
+    let logins = Arc::new(AtomicUsize::new(0));
+    let valid = Arc::new(Mutex::new(Vec::new()));
+    let (counter, sessions) = (logins.clone(), valid.clone());
+    let addr = serve(move |req| {
+        if req.path == "/login" {
+            let n = counter.fetch_add(1, Ordering::SeqCst) + 1;
+            let cookie = format!("3x-ui=s{n}");
+            sessions.lock().unwrap().push(cookie.clone());
+            return ok(json!(null)).with_header(
+                "Set-Cookie",
+                &format!("{cookie}; Path=/; Max-Age={max_age}; HttpOnly"),
+            );
+        }
+        let cookie = req.header("cookie").unwrap_or_default().to_string();
+        if sessions.lock().unwrap().contains(&cookie) {
+            ok(json!([]))
+        } else {
+            MockResponse::status(404)
+        }
+    })
+    .await;
+    Panel {
+        url: format!("http://{addr}/"),
+        logins,
+        valid,
+    }
+}
+
+fn store_path() -> PathBuf {
+    std::env::temp_dir().join(format!("rustix3-session-{}.json", uuid::Uuid::new_v4()))
+}
+
+fn options(store: &Arc<FileSessionStore>) -> ClientOptions {
+    ClientOptions {
+        session_store: Some(store.clone()),
+        ..ClientOptions::default()
+    }
+}
+
+#[tokio::test]
+async fn stored_session_is_reused_by_the_next_client() {
+    let panel = panel(3600).await;
+    let store = Arc::new(FileSessionStore::new(store_path()));
+
+    Client::new_with_options("admin", "admin", &panel.url, options(&store))
+        .await
+        .unwrap();
+    let client = Client::new_with_options("admin", "admin", &panel.url, options(&store))
+        .await
+        .unwrap();
+    client.get_inbounds_list().await.unwrap();
+    assert_eq!(panel.logins.load(Ordering::SeqCst), 1);
+
+    let key = format!("{}#admin", panel.url);
+    let saved = store.load(&key).await.unwrap().unwrap();
+    assert_eq!(saved.cookie.as_deref(), Some("3x-ui=s1"));
+    assert!(saved.expires_at.is_some());
+    let _ = std::fs::remove_file(store.path());
+}
+
+#[tokio::test]
+async fn rejected_session_falls_back_to_login() {
+    let panel = panel(3600).await;
+    let store = Arc::new(FileSessionStore::new(store_path()));
+
+    Client::new_with_options("admin", "admin", &panel.url, options(&store))
+        .await
+        .unwrap();
+    // the panel restarted and forgot every session
+    panel.valid.lock().unwrap().clear();
+
+    let client = Client::new_with_options("admin", "admin", &panel.url, options(&store))
+        .await
+        .unwrap();
+    client.get_inbounds_list().await.unwrap();
+    assert_eq!(panel.logins.load(Ordering::SeqCst), 2);
+    let _ = std::fs::remove_file(store.path());
+}
+
+#[tokio::test]
+async fn expired_session_is_not_validated() {
+    let panel = panel(3600).await;
+    let store = Arc::new(FileSessionStore::new(store_path()));
+    let key = format!("{}#admin", panel.url);
+    let stale = StoredSession {
+        cookie: Some("3x-ui=old".into()),
+        token: None,
+        expires_at: Some(1),
+    };
+    store.save(&key, &stale).await.unwrap();
+
+    Client::new_with_options("admin", "admin", &panel.url, options(&store))
+        .await
+        .unwrap();
+    assert_eq!(panel.logins.load(Ordering::SeqCst), 1);
+    let saved = store.load(&key).await.unwrap().unwrap();
+    assert_eq!(saved.cookie.as_deref(), Some("3x-ui=s1"));
+    let _ = std::fs::remove_file(store.path());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn store_file_is_owner_only_even_over_a_stale_temp_file() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = store_path();
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, "{}").unwrap();
+    std::fs::set_permissions(&tmp, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+    let store = FileSessionStore::new(&path);
+    let session = StoredSession {
+        cookie: Some("3x-ui=s1".into()),
+        token: None,
+        expires_at: None,
+    };
+    store.save("key", &session).await.unwrap();
+
+    let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+    assert!(!tmp.exists());
+    let _ = std::fs::remove_file(&path);
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use common::{Requests, ok, serve_recording, token_client};
+use serde_json::{Value, json};
+use std::net::SocketAddr;
+
+fn all_settings() -> Value {
+    json!({
+        "webListen": "",
+        "webDomain": "",
+        "webPort": 2053,
+        "webCertFile": "/etc/ssl/panel.crt",
+        "webKeyFile": "/etc/ssl/panel.key",
+        "webBasePath": "/panel-x/",
+        "sessionMaxAge": 60,
+        "tgBotEnable": true,
+        "tgBotToken": "123:abc",
+        "tgBotAPIServer": "",
+        "tgBotChatId": "42",
+        "tgRunTime": "@daily",
+        "timeLocation": "Europe/Berlin",
+        "subEnable": true,
+        "subPort": 2096,
+        "subPath": "/sub/",
+        "subDomain": "sub.example.com",
+        "subEncrypt": true,
+        "subJsonEnable": false,
+        "subJsonURI": "",
+        "ldapEnable": false,
+        "pageSize": 50
+    })
+}
+
+async fn panel() -> (SocketAddr, Requests) {
+    serve_recording(|req| match req.path.as_str() {
+        "/panel/setting/all" => ok(all_settings()),
+        "/panel/setting/getDefaultJsonConfig" => ok(json!({
+            "log": { "loglevel": "warning" },
+            "outbounds": [{ "protocol": "freedom", "tag": "direct" }],
+            "policy": { "levels": {} }
+        })),
+        _ => ok(json!(null)),
+    })
+    .await
+}
+
+#[tokio::test]
+async fn settings_round_trip_keeps_unknown_keys() {
+    let (addr, requests) = panel().await;
+    let client = token_client(addr);
+
+    let mut settings = client.get_all_settings().await.unwrap();
+    assert_eq!(settings.web_port, 2053);
+    assert_eq!(settings.web_base_path, "/panel-x/");
+    assert_eq!(settings.tg_bot_chat_id, "42");
+    assert_eq!(settings.sub_domain, "sub.example.com");
+    assert_eq!(settings.time_location, "Europe/Berlin");
+    assert_eq!(settings.extra["pageSize"], json!(50));
+
+    settings.sub_port = 8443;
+    client.update_settings(&settings).await.unwrap();
+    client.restart_panel().await.unwrap();
+
+    let requests = requests.lock().unwrap();
+    let update = &requests[1];
+    assert_eq!(update.path, "/panel/setting/update");
+    assert_eq!(update.method, "POST");
+    let body: Value = serde_json::from_str(&update.body_str()).unwrap();
+    assert_eq!(body["subPort"], json!(8443));
+    assert_eq!(body["tgBotAPIServer"], json!(""));
+    assert_eq!(body["ldapEnable"], json!(false));
+    assert_eq!(body["pageSize"], json!(50));
+    assert_eq!(requests[2].path, "/panel/setting/restartPanel");
+}
+
+#[tokio::test]
+async fn default_xray_config_is_typed() {
+    let (addr, _) = panel().await;
+    let client = token_client(addr);
+
+    let config = client.get_default_xray_config().await.unwrap();
+    assert_eq!(config.log.unwrap().loglevel.as_deref(), Some("warning"));
+    assert_eq!(config.outbounds.unwrap()[0].tag.as_deref(), Some("direct"));
+    assert!(config.extra.contains_key("policy"));
+}
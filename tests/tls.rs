@@ -0,0 +1,149 @@
+mod common;
+
+use common::{ok, serve_tls};
+use rcgen::{BasicConstraints, Certificate, CertificateParams, IsCa, KeyPair};
+use rustix3::tls::cert_fingerprint_sha256;
+use rustix3::{Client, ClientOptions, TlsOptions};
+use rustls::RootCertStore;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
+use rustls::server::WebPkiClientVerifier;
+use serde_json::json;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+struct Pki {
+    ca: Certificate,
+    ca_key: KeyPair,
+}
+
+impl Pki {
+    fn new() -> Self {
+        let ca_key = KeyPair::generate().unwrap();
+        let mut params = CertificateParams::new(Vec::<String>::new()).unwrap();
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let ca = params.self_signed(&ca_key).unwrap();
+        Self { ca, ca_key }
+    }
+
+    fn issue(&self, names: &[&str]) -> (Certificate, KeyPair) {
+        let key = KeyPair::generate().unwrap();
+        let names = names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        let cert = CertificateParams::new(names)
+            .unwrap()
+            .signed_by(&key, &self.ca, &self.ca_key)
+            .unwrap();
+        (cert, key)
+    }
+}
+
+fn key_der(key: &KeyPair) -> PrivateKeyDer<'static> {
+    PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(key.serialize_der()))
+}
+
+fn provider() -> Arc<rustls::crypto::CryptoProvider> {
+    Arc::new(rustls::crypto::ring::default_provider())
+}
+
+async fn panel(pki: &Pki, client_ca: Option<&Certificate>) -> (SocketAddr, Certificate) {
+    let (cert, key) = pki.issue(&["localhost", "127.0.0.1"]);
+    let builder = rustls::ServerConfig::builder_with_provider(provider())
+        .with_safe_default_protocol_versions()
+        .unwrap();
+    let builder = match client_ca {
+        Some(ca) => {
+            let mut roots = RootCertStore::empty();
+            roots.add(ca.der().clone()).unwrap();
+            let verifier = WebPkiClientVerifier::builder_with_provider(roots.into(), provider())
+                .build()
+                .unwrap();
+            builder.with_client_cert_verifier(verifier)
+        }
+        None => builder.with_no_client_auth(),
+    };
+    let config = builder
+        .with_single_cert(
+            vec![CertificateDer::from(cert.der().to_vec())],
+            key_der(&key),
+        )
+        .unwrap();
+    let addr = serve_tls(Arc::new(config), |_| ok(json!(null))).await;
+    (addr, cert)
+}
+
+async fn connect(addr: SocketAddr, tls: TlsOptions) -> rustix3::Result<Client> {
+    let options = ClientOptions {
+        tls,
+        retry_count: 0,
+        ..ClientOptions::default()
+    };
+    Client::new_with_options("admin", "admin", format!("https://{addr}/"), options).await
+}
+
+#[tokio::test]
+async fn untrusted_certificate_is_rejected() {
+    let pki = Pki::new();
+    let (addr, _) = panel(&pki, None).await;
+    assert!(connect(addr, TlsOptions::default()).await.is_err());
+}
+
+#[tokio::test]
+async fn extra_root_ca_is_trusted() {
+    let pki = Pki::new();
+    let (addr, _) = panel(&pki, None).await;
+    let tls = TlsOptions {
+        root_ca_pems: vec![pki.ca.pem().into_bytes()],
+        ..TlsOptions::default()
+    };
+    connect(addr, tls).await.unwrap();
+}
+
+#[tokio::test]
+async fn accept_invalid_certs_skips_validation() {
+    let pki = Pki::new();
+    let (addr, _) = panel(&pki, None).await;
+    let tls = TlsOptions {
+        accept_invalid_certs: true,
+        ..TlsOptions::default()
+    };
+    connect(addr, tls).await.unwrap();
+}
+
+#[tokio::test]
+async fn pinned_fingerprint_must_match() {
+    let pki = Pki::new();
+    let (addr, cert) = panel(&pki, None).await;
+
+    let pin = cert_fingerprint_sha256(cert.der());
+    let tls = TlsOptions {
+        pinned_sha256: vec![pin],
+        ..TlsOptions::default()
+    };
+    connect(addr, tls).await.unwrap();
+
+    let wrong = cert_fingerprint_sha256(pki.ca.der());
+    let tls = TlsOptions {
+        pinned_sha256: vec![wrong],
+        ..TlsOptions::default()
+    };
+    assert!(connect(addr, tls).await.is_err());
+}
+
+#[tokio::test]
+async fn client_certificate_is_presented_for_mtls() {
+    let pki = Pki::new();
+    let (addr, _) = panel(&pki, Some(&pki.ca)).await;
+    let (client_cert, client_key) = pki.issue(&["rustix3-client"]);
+    let identity = format!("{}{}", client_cert.pem(), client_key.serialize_pem());
+
+    let without = TlsOptions {
+        root_ca_pems: vec![pki.ca.pem().into_bytes()],
+        ..TlsOptions::default()
+    };
+    assert!(connect(addr, without.clone()).await.is_err());
+
+    let with = TlsOptions {
+        client_identity_pem: Some(identity.into_bytes()),
+        ..without
+    };
+    connect(addr, with).await.unwrap();
+}
@@ -0,0 +1,153 @@
+mod common;
+
+use common::{MockResponse, ok, serve};
+use rustix3::error::Error;
+use rustix3::{AuthToken, Client, ClientOptions, FnToken};
+use serde_json::json;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
+
+#[tokio::test]
+async fn token_client_sends_bearer_header_without_login() {
+    let addr = serve(|req| {
+        assert_ne!(req.path, "/login");
+        if req.header("authorization") == Some("Bearer api-token") {
+            ok(json!([]))
+        } else {
+            MockResponse::status(401)
+        }
+    })
+    .await;
+
+    let client = Client::from_token(
+        AuthToken::new("api-token"),
+        format!("http://{addr}/"),
+        ClientOptions::default(),
+    )
+    .unwrap();
+    assert!(client.get_inbounds_list().await.unwrap().is_empty());
+    assert_eq!(client.token().unwrap().token, "api-token");
+    assert!(!format!("{client:?}").contains("api-token"));
+}
+
+#[tokio::test]
+async fn rejected_token_without_credentials_reports_status() {
+    let logins = Arc::new(AtomicUsize::new(0));
+    let addr = {
+        let logins = logins.clone();
+        serve(move |req| {
+            if req.path.starts_with("/login") {
+                logins.fetch_add(1, Ordering::SeqCst);
+            }
+            MockResponse::status(401)
+        })
+        .await
+    };
+
+    let client = Client::from_token(
+        AuthToken::new("stale"),
+        format!("http://{addr}/"),
+        ClientOptions::default(),
+    )
+    .unwrap();
+    let err = client.get_inbounds_list().await.unwrap_err();
+    assert!(
+        matches!(err, Error::HttpStatus { status, .. } if status == 401),
+        "{err:?}"
+    );
+    assert_eq!(logins.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn token_is_refreshed_before_expiry() {
+    let logins = Arc::new(AtomicUsize::new(0));
+    let counter = logins.clone();
+    let addr = serve(move |req| {
+        if req.path == "/login" {
+            let n = counter.fetch_add(1, Ordering::SeqCst) + 1;
+            // the first token is already inside the refresh margin
+            let expires_at = if n == 1 { now() + 10 } else { now() + 3600 };
+            return ok(json!({ "token": format!("t{n}"), "expiresAt": expires_at }));
+        }
+        if req.header("authorization") == Some("Bearer t2") {
+            ok(json!([]))
+        } else {
+            MockResponse::status(401)
+        }
+    })
+    .await;
+
+    let client = Client::new("admin", "admin", format!("http://{addr}/"))
+        .await
+        .unwrap();
+    assert_eq!(client.token().unwrap().token, "t1");
+    client.get_inbounds_list().await.unwrap();
+    assert_eq!(client.token().unwrap().token, "t2");
+    assert_eq!(logins.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn token_only_client_refreshes_through_provider() {
+    let addr = serve(|req| {
+        assert_ne!(req.path, "/login");
+        if req.header("authorization") == Some("Bearer fresh") {
+            ok(json!([]))
+        } else {
+            MockResponse::status(401)
+        }
+    })
+    .await;
+    let fetched = Arc::new(AtomicUsize::new(0));
+    let provider = {
+        let fetched = fetched.clone();
+        FnToken::new(move || {
+            fetched.fetch_add(1, Ordering::SeqCst);
+            async move { Ok(AuthToken::new("fresh").with_expires_at(now() + 3600)) }
+        })
+    };
+
+    let client = Client::builder(format!("http://{addr}/"))
+        .token(AuthToken::new("stale").with_expires_at(now() + 10))
+        .token_provider(Arc::new(provider))
+        .build()
+        .unwrap();
+    client.get_inbounds_list().await.unwrap();
+    client.get_inbounds_list().await.unwrap();
+    assert_eq!(client.token().unwrap().token, "fresh");
+    assert_eq!(fetched.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn expired_token_without_provider_fails_before_sending() {
+    let hits = Arc::new(AtomicUsize::new(0));
+    let addr = {
+        let hits = hits.clone();
+        serve(move |_| {
+            hits.fetch_add(1, Ordering::SeqCst);
+            ok(json!([]))
+        })
+        .await
+    };
+
+    let client = Client::from_token(
+        AuthToken::new("stale").with_expires_at(now() - 1),
+        format!("http://{addr}/"),
+        ClientOptions::default(),
+    )
+    .unwrap();
+    let err = client.get_inbounds_list().await.unwrap_err();
+    assert!(matches!(err, Error::TokenExpired), "{err:?}");
+    assert_eq!(hits.load(Ordering::SeqCst), 0);
+
+    // Inside the refresh margin but not yet expired, the token is still used.
+    client.set_token(Some(AuthToken::new("soon").with_expires_at(now() + 10)));
+    client.get_inbounds_list().await.unwrap();
+}
@@ -0,0 +1,131 @@
+#![cfg(feature = "tracing")]
+
+mod common;
+
+use common::{MockResponse, ok, serve, token_builder};
+use rustix3::EmailField;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use tokio::time::Duration;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::{Layer, Registry};
+
+type Spans = Arc<Mutex<BTreeMap<u64, BTreeMap<String, String>>>>;
+
+#[derive(Clone, Default)]
+struct Capture(Spans);
+
+struct Fields<'a>(&'a mut BTreeMap<String, String>);
+
+impl Visit for Fields<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.insert(
+            field.name().to_string(),
+            format!("{value:?}").trim_matches('"').into(),
+        );
+    }
+}
+
+impl<S: tracing::Subscriber> Layer<S> for Capture {
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, _ctx: Context<'_, S>) {
+        let mut fields = BTreeMap::new();
+        fields.insert("name".into(), attrs.metadata().name().into());
+        attrs.record(&mut Fields(&mut fields));
+        self.0.lock().unwrap().insert(id.into_u64(), fields);
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, _ctx: Context<'_, S>) {
+        if let Some(fields) = self.0.lock().unwrap().get_mut(&id.into_u64()) {
+            values.record(&mut Fields(fields));
+        }
+    }
+}
+
+fn stats(email: &str) -> serde_json::Value {
+    json!({
+        "id": 1, "inboundId": 3, "enable": true, "email": email, "up": 0, "down": 0,
+        "expiryTime": 0, "total": 0, "reset": 0
+    })
+}
+
+async fn captured_span(emails: EmailField) -> BTreeMap<String, String> {
+    let attempts = Arc::new(Mutex::new(0));
+    let server_attempts = attempts.clone();
+    let addr = serve(move |_| {
+        let mut attempts = server_attempts.lock().unwrap();
+        *attempts += 1;
+        if *attempts == 1 {
+            MockResponse::status(503)
+        } else {
+            ok(stats("alice@example.com"))
+        }
+    })
+    .await;
+
+    let capture = Capture::default();
+    let spans = capture.0.clone();
+    let _guard = tracing::subscriber::set_default(Registry::default().with(capture));
+
+    let client = token_builder(addr)
+        .retry_base_delay(Duration::from_millis(1))
+        .trace_emails(emails)
+        .build()
+        .unwrap();
+    client
+        .get_client_traffic_by_email("alice@example.com")
+        .await
+        .unwrap();
+
+    let spans = spans.lock().unwrap();
+    spans
+        .values()
+        .find(|s| s["name"] == "panel_api")
+        .cloned()
+        .unwrap()
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn api_call_span_carries_request_details() {
+    let span = captured_span(EmailField::Plain).await;
+    assert_eq!(span["endpoint"], "get_client_traffic_by_email");
+    assert_eq!(span["http.method"], "GET");
+    assert_eq!(span["email"], "alice@example.com");
+    assert_eq!(span["attempt"], "2");
+    assert_eq!(span["status"], "200");
+    assert!(span.contains_key("latency_ms"));
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn emails_are_hashed_or_redacted() {
+    let hashed = captured_span(EmailField::Hashed).await;
+    assert_eq!(
+        hashed["email"],
+        EmailField::Hashed.render("alice@example.com")
+    );
+    assert_eq!(hashed["email"].len(), 16);
+    assert!(!hashed["email"].contains('@'));
+
+    let redacted = captured_span(EmailField::Redacted).await;
+    assert_eq!(redacted["email"], "<redacted>");
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn inbound_id_is_recorded() {
+    let addr = serve(|_| ok(json!(null))).await;
+    let capture = Capture::default();
+    let spans = capture.0.clone();
+    let _guard = tracing::subscriber::set_default(Registry::default().with(capture));
+
+    let client = token_builder(addr).build().unwrap();
+    client.delete_depleted_clients(7).await.unwrap();
+
+    let spans = spans.lock().unwrap();
+    let span = spans.values().find(|s| s["name"] == "panel_api").unwrap();
+    assert_eq!(span["endpoint"], "delete_depleted_clients");
+    assert_eq!(span["inbound_id"], "7");
+    assert_eq!(span["http.method"], "POST");
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use common::{fail, ok, serve};
+use rustix3::error::Error;
+use rustix3::two_factor::totp;
+use rustix3::{Client, ClientOptions, TwoFactor};
+use serde_json::{Value, json};
+
+// RFC 6238 appendix B, SHA-1 key "12345678901234567890"
+const RFC_SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+#[test]
+fn totp_matches_rfc6238_vectors() {
+    assert_eq!(totp(RFC_SECRET, 59).unwrap(), "287082");
+    assert_eq!(totp(RFC_SECRET, 1111111109).unwrap(), "081804");
+    assert_eq!(totp(RFC_SECRET, 2000000000).unwrap(), "279037");
+}
+
+#[test]
+fn totp_rejects_invalid_secret() {
+    assert!(totp("not base32!", 59).is_err());
+}
+
+#[tokio::test]
+async fn login_sends_configured_code() {
+    let addr = serve(|req| {
+        let body: Value = serde_json::from_slice(&req.body).unwrap_or_default();
+        if req.path == "/login" && body["twoFactorCode"] == "123456" {
+            ok(json!(null))
+        } else {
+            fail("invalid 2fa code")
+        }
+    })
+    .await;
+
+    let options = ClientOptions {
+        two_factor: Some(TwoFactor::callback(|| "123456".into())),
+        ..ClientOptions::default()
+    };
+    Client::new_with_options("admin", "admin", format!("http://{addr}/"), options)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn missing_code_reports_two_factor_required() {
+    let addr = serve(|req| match req.path.as_str() {
+        "/getTwoFactorEnable" => ok(json!(true)),
+        _ => fail("invalid 2fa code"),
+    })
+    .await;
+
+    let err = Client::new("admin", "admin", format!("http://{addr}/"))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::TwoFactorRequired), "{err:?}");
+}
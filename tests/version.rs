@@ -0,0 +1,98 @@
+mod common;
+
+use common::{MockResponse, ok, serve, token_builder, token_client};
+use rustix3::error::Error;
+use rustix3::{Capabilities, PanelVersion};
+use serde_json::json;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+fn page(html: &str) -> MockResponse {
+    MockResponse {
+        status: 200,
+        headers: vec![("Content-Type".into(), "text/html; charset=utf-8".into())],
+        body: html.as_bytes().to_vec(),
+    }
+}
+
+async fn panel(html: &'static str, paths: Arc<Mutex<Vec<String>>>) -> SocketAddr {
+    serve(move |req| {
+        paths.lock().unwrap().push(req.path.clone());
+        match req.path.as_str() {
+            "/" => page(html),
+            _ => ok(json!({ "echConfigList": "list", "echServerKeys": "keys" })),
+        }
+    })
+    .await
+}
+
+#[tokio::test]
+async fn unsupported_endpoint_fails_without_request() {
+    let paths = Arc::new(Mutex::new(Vec::new()));
+    let html = r#"<html><script src="/assets/js/app.js?2.6.3"></script></html>"#;
+    let client = token_client(panel(html, paths.clone()).await);
+
+    let err = client.get_new_vless_enc().await.unwrap_err();
+    assert!(matches!(
+        err,
+        Error::Unsupported { endpoint: "get_new_vless_enc", min_version } if min_version == PanelVersion::new(2, 8, 0)
+    ));
+    client.get_new_ech_cert().await.unwrap();
+    assert_eq!(
+        client.panel_version().await.unwrap(),
+        Some(PanelVersion::new(2, 6, 3))
+    );
+
+    assert_eq!(
+        *paths.lock().unwrap(),
+        ["/", "/panel/api/server/getNewEchCert"]
+    );
+}
+
+#[tokio::test]
+async fn undetected_version_does_not_gate_calls() {
+    let paths = Arc::new(Mutex::new(Vec::new()));
+    let client = token_client(panel("<html></html>", paths.clone()).await);
+
+    assert_eq!(client.panel_version().await.unwrap(), None);
+    assert!(
+        client
+            .capabilities()
+            .await
+            .unwrap()
+            .supports("get_new_vless_enc")
+    );
+    client.get_new_ech_cert().await.unwrap();
+    assert_eq!(paths.lock().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn configured_version_skips_detection() {
+    let paths = Arc::new(Mutex::new(Vec::new()));
+    let addr = panel("<html></html>", paths.clone()).await;
+    let client = token_builder(addr)
+        .panel_version(PanelVersion::new(2, 5, 0))
+        .build()
+        .unwrap();
+
+    let err = client.get_new_ech_cert().await.unwrap_err();
+    assert!(matches!(err, Error::Unsupported { .. }));
+    assert!(paths.lock().unwrap().is_empty());
+}
+
+#[test]
+fn versions_parse_and_compare() {
+    let v: PanelVersion = "v2.6.3".parse().unwrap();
+    assert_eq!(v, PanelVersion::new(2, 6, 3));
+    assert_eq!(v.to_string(), "2.6.3");
+    assert!(PanelVersion::new(2, 10, 0) > PanelVersion::new(2, 9, 9));
+    assert!("2.6".parse::<PanelVersion>().is_err());
+    assert!("2.6.3.1".parse::<PanelVersion>().is_err());
+
+    let caps = Capabilities {
+        version: Some(PanelVersion::new(2, 8, 0)),
+    };
+    assert!(caps.supports("get_new_mlkem768"));
+    assert!(caps.supports("get_inbounds_list"));
+    assert_eq!(Capabilities::min_version("get_inbounds_list"), None);
+}
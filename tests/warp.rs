@@ -0,0 +1,108 @@
+mod common;
+
+use common::{Requests, ok, serve_recording, token_client};
+use serde_json::{Value, json};
+use std::net::SocketAddr;
+
+const PRIVATE_KEY: &str = "aGVsbG8td2FycC1wcml2YXRlLWtleS0zMmJ5dGVzIT0=";
+const PUBLIC_KEY: &str = "cHVibGljLWtleS1mb3Itd2FycC1kZXZpY2UtMzJieXQ=";
+
+fn warp_data(license: &str) -> Value {
+    json!({
+        "access_token": "token-1",
+        "device_id": "device-1",
+        "license_key": license,
+        "private_key": PRIVATE_KEY
+    })
+}
+
+fn device() -> Value {
+    json!({
+        "id": "device-1",
+        "type": "a",
+        "key": PUBLIC_KEY,
+        "account": {
+            "id": "account-1",
+            "account_type": "free",
+            "warp_plus": true,
+            "premium_data": 0,
+            "quota": 0,
+            "license": "lic-free"
+        },
+        "config": {
+            "client_id": "AQID",
+            "peers": [{
+                "public_key": "bmXOC+F1FxEMF9dyiK2H5/1SUtzH0JuVo51h2wPfgyo=",
+                "endpoint": { "v4": "162.159.192.1:0", "v6": "[2606:4700:d0::a29f:c001]:0", "host": "engage.cloudflareclient.com:2408" }
+            }],
+            "interface": { "addresses": { "v4": "172.16.0.2", "v6": "2606:4700:110:8a36::2" } },
+            "services": { "http_proxy": "172.16.0.1:2480" }
+        },
+        "warp_enabled": true
+    })
+}
+
+/// Mock of the panel's WARP actions; `obj` is JSON text, as the panel sends it.
+async fn panel(registered: bool) -> (SocketAddr, Requests) {
+    serve_recording(move |req| match req.path.as_str() {
+        "/panel/xray/warp/data" if registered => ok(json!(warp_data("lic-free").to_string())),
+        "/panel/xray/warp/config" => ok(json!(device().to_string())),
+        "/panel/xray/warp/reg" => ok(json!(
+            json!({ "data": warp_data("lic-free"), "config": device() }).to_string()
+        )),
+        "/panel/xray/warp/license" => ok(json!(warp_data("lic-plus").to_string())),
+        _ => ok(json!("")),
+    })
+    .await
+}
+
+#[tokio::test]
+async fn register_and_license_warp() {
+    let (addr, requests) = panel(false).await;
+    let client = token_client(addr);
+
+    assert!(client.get_warp_data().await.unwrap().is_none());
+
+    let registration = client.reg_warp(PRIVATE_KEY, PUBLIC_KEY).await.unwrap();
+    assert_eq!(registration.data.device_id, "device-1");
+    assert_eq!(registration.data.private_key, PRIVATE_KEY);
+    let config = &registration.config.config;
+    assert_eq!(config.client_id, "AQID");
+    assert_eq!(config.interface.addresses.v4, "172.16.0.2");
+    assert_eq!(
+        config.peers[0].endpoint.host.as_deref(),
+        Some("engage.cloudflareclient.com:2408")
+    );
+    assert!(config.extra.contains_key("services"));
+
+    let data = client.set_warp_license("lic-plus").await.unwrap();
+    assert_eq!(data.license_key, "lic-plus");
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests[1].path, "/panel/xray/warp/reg");
+    assert_eq!(requests[1].method, "POST");
+    assert!(requests[1].body_str().starts_with("privateKey="));
+    assert!(requests[1].body_str().contains("&publicKey="));
+    assert_eq!(requests[2].body_str(), "license=lic-plus");
+}
+
+#[tokio::test]
+async fn read_and_delete_warp() {
+    let (addr, requests) = panel(true).await;
+    let client = token_client(addr);
+
+    let data = client.get_warp_data().await.unwrap().unwrap();
+    assert_eq!(data.access_token, "token-1");
+
+    let device = client.get_warp_config().await.unwrap();
+    let account = device.account.unwrap();
+    assert_eq!(account.account_type.as_deref(), Some("free"));
+    assert_eq!(account.warp_plus, Some(true));
+    assert_eq!(device.key, PUBLIC_KEY);
+
+    client.del_warp_data().await.unwrap();
+    assert_eq!(
+        requests.lock().unwrap().last().unwrap().path,
+        "/panel/xray/warp/del"
+    );
+}
@@ -0,0 +1,126 @@
+mod common;
+
+use common::{MockRequest, Requests, ok, serve_recording, token_client};
+use reqwest::Url;
+use rustix3::models::ConfigOutbound;
+use serde_json::{Value, json};
+use std::net::SocketAddr;
+
+fn template() -> Value {
+    template_with_strategy("IPIfNonMatch")
+}
+
+fn template_with_strategy(domain_strategy: &str) -> Value {
+    json!({
+        "log": { "access": "none", "loglevel": "warning" },
+        "api": { "services": ["HandlerService"], "tag": "api" },
+        "inbounds": [{ "listen": "127.0.0.1", "port": 62789, "protocol": "dokodemo-door", "tag": "api" }],
+        "outbounds": [
+            { "protocol": "freedom", "settings": { "domainStrategy": "AsIs" }, "tag": "direct" },
+            { "protocol": "blackhole", "settings": {}, "tag": "blocked" }
+        ],
+        "policy": { "system": { "statsInboundUplink": true } },
+        "routing": {
+            "domainStrategy": domain_strategy,
+            "balancers": [],
+            "rules": [{ "type": "field", "outboundTag": "blocked", "ip": ["geoip:private"] }]
+        },
+        "stats": {}
+    })
+}
+
+async fn panel(xray_result: &'static str) -> (SocketAddr, Requests) {
+    panel_with(template(), xray_result).await
+}
+
+async fn panel_with(template: Value, xray_result: &'static str) -> (SocketAddr, Requests) {
+    serve_recording(move |req| match req.path.as_str() {
+        "/panel/xray/" => {
+            let obj = json!({ "xraySetting": template, "inboundTags": ["inbound-443"] });
+            ok(json!(obj.to_string()))
+        }
+        "/panel/xray/getXrayResult" => ok(json!(xray_result)),
+        _ => ok(json!(null)),
+    })
+    .await
+}
+
+fn form_field(req: &MockRequest, name: &str) -> String {
+    let url = Url::parse(&format!("http://x/?{}", req.body_str())).unwrap();
+    url.query_pairs()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.into_owned())
+        .unwrap()
+}
+
+#[tokio::test]
+async fn template_round_trip_keeps_unknown_sections() {
+    let (addr, requests) = panel("").await;
+    let client = token_client(addr);
+
+    let config = client.get_xray_template().await.unwrap();
+    assert_eq!(config.outbounds.as_ref().unwrap().len(), 2);
+    assert!(config.extra.contains_key("policy"));
+    client.update_xray_template(&config).await.unwrap();
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests[1].path, "/panel/xray/update");
+    let sent: Value = serde_json::from_str(&form_field(&requests[1], "xraySetting")).unwrap();
+    assert_eq!(sent, template());
+}
+
+#[tokio::test]
+async fn unlisted_routing_domain_strategy_is_kept() {
+    let template = template_with_strategy("IPIfNonMatchCustom");
+    let (addr, requests) = panel_with(template.clone(), "").await;
+    let client = token_client(addr);
+
+    let config = client.get_xray_template().await.unwrap();
+    let routing = config.routing.as_ref().unwrap();
+    assert_eq!(
+        routing.domain_strategy.as_deref(),
+        Some("IPIfNonMatchCustom")
+    );
+    client.update_xray_template(&config).await.unwrap();
+
+    let requests = requests.lock().unwrap();
+    let sent: Value = serde_json::from_str(&form_field(&requests[1], "xraySetting")).unwrap();
+    assert_eq!(sent, template);
+}
+
+#[tokio::test]
+async fn apply_saves_restarts_and_reports() {
+    let (addr, requests) = panel("failed to start: bad outbound").await;
+    let client = token_client(addr);
+
+    let result = client
+        .apply_xray_template(|config| {
+            config
+                .outbounds
+                .get_or_insert_default()
+                .push(ConfigOutbound {
+                    protocol: Some("socks".into()),
+                    settings: Some(json!({ "servers": [{ "address": "10.0.0.1", "port": 1080 }] })),
+                    tag: Some("chain".into()),
+                    extra: Default::default(),
+                });
+        })
+        .await
+        .unwrap();
+    assert_eq!(result.as_deref(), Some("failed to start: bad outbound"));
+
+    let requests = requests.lock().unwrap();
+    let paths: Vec<_> = requests.iter().map(|r| r.path.as_str()).collect();
+    assert_eq!(
+        paths,
+        [
+            "/panel/xray/",
+            "/panel/xray/update",
+            "/panel/api/server/restartXrayService",
+            "/panel/xray/getXrayResult"
+        ]
+    );
+    let sent: Value = serde_json::from_str(&form_field(&requests[1], "xraySetting")).unwrap();
+    assert_eq!(sent["outbounds"][2]["tag"], json!("chain"));
+    assert_eq!(sent["routing"]["domainStrategy"], json!("IPIfNonMatch"));
+}